@@ -1,3 +1,7 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
 use color_eyre::Result;
 use crossterm::event::{
     self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
@@ -6,55 +10,405 @@ use crossterm::event::{
 use ratatui::{
     layout::{Constraint, Layout, Rect},
     style::{Color, Style, Stylize},
-    widgets::{Axis, Block, Chart, Clear, Dataset, GraphType, Row, Table, TableState},
+    widgets::{
+        Axis, Block, Chart, Clear, Dataset, GraphType, List, ListItem, ListState, Row, Table,
+        TableState,
+    },
     DefaultTerminal, Frame,
 };
-use sysinfo::Signal;
-use sysinfo::{ProcessesToUpdate, System};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
 use tui_textarea::TextArea;
-use users::get_user_by_uid;
+use users::{get_current_uid, get_user_by_uid};
+
+use crate::audit;
+use crate::columns::{
+    self, is_kernel_thread, process_cmd, process_user, Column, MemoryMetric, SortKey,
+};
+use crate::config::{Config, Preset};
+use crate::details;
+use crate::net;
+use crate::procfs;
+use crate::protect::Protection;
+use crate::query::{Pattern, PatternMode, Query};
+use crate::sched;
+use crate::signals::{self, Scope, SignalInfo, Target};
+use crate::tree::{subtree, subtrees};
+
+/// A kill modal action on a protected process, waiting for its name to be typed.
+#[derive(Debug, Clone, Copy)]
+enum Confirm {
+    Signal(i32),
+    Escalate,
+}
+
+/// A change the table makes to the picked processes, kept while a single protected
+/// target waits for its name to be typed.
+#[derive(Debug, Clone)]
+enum Change {
+    /// Nice value moved by `+`/`-`, clamped to the valid range.
+    NiceBy(i32),
+    /// Nice value set in the renice dialog.
+    NiceTo(i32),
+    /// I/O priority, optionally for all descendants too.
+    IoPriority {
+        priority: sched::IoPriority,
+        subtree: bool,
+    },
+    /// Scheduling policy with its real-time priority.
+    Policy {
+        policy: sched::Policy,
+        priority: i32,
+    },
+    /// CPUs the process may run on, for its main thread or all threads.
+    Affinity { cpus: Vec<usize>, all_threads: bool },
+}
+
+impl Change {
+    fn describe(&self) -> String {
+        match *self {
+            Change::NiceBy(delta) => format!("change its nice value by {:+}", delta),
+            Change::NiceTo(nice) => format!("set its nice value to {}", nice),
+            Change::IoPriority { priority, .. } => {
+                format!("set its I/O priority to {}", priority)
+            }
+            Change::Policy { policy, priority } => {
+                format!("set its policy to {}", policy_setting(policy, priority))
+            }
+            Change::Affinity {
+                ref cpus,
+                all_threads,
+            } => format!(
+                "set its CPU affinity to {}",
+                affinity_setting(cpus, all_threads)
+            ),
+        }
+    }
+}
+
+/// Outcome of sending a signal to (or renicing) one process.
+#[derive(Debug)]
+struct SignalResult {
+    pid: sysinfo::Pid,
+    name: String,
+    result: std::result::Result<(), String>,
+}
+
+/// How long a status message replaces the help line.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long to wait before checking that signalled processes have exited.
+const EXIT_CHECK_DELAY: Duration = Duration::from_secs(3);
+/// CPUs per row of the affinity grid.
+const AFFINITY_COLUMNS: usize = 8;
+
+/// Keys listed in the help overlay, by section.
+const HELP: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Table",
+        &[
+            ("q/Esc", "Quit"),
+            ("j/k", "Move"),
+            ("s", "Toggle search (Tab in search: name or cmd/exe/cwd)"),
+            ("/", "Find, n/N next/previous match"),
+            ("o/r", "Sort column / reverse"),
+            ("c", "Columns"),
+            ("F/S/1-9", "Presets: pick / save / apply"),
+            ("u/U", "My processes / processes of a user"),
+            ("K", "Hide kernel threads"),
+            ("Z", "Stopped processes only"),
+            ("M", "Memory column RSS/PSS/USS"),
+            ("Enter", "Details"),
+            ("H", "History"),
+            ("?", "This help"),
+        ],
+    ),
+    (
+        "Tagging",
+        &[
+            ("Space", "Tag selected"),
+            ("a", "Tag all shown"),
+            ("T", "Tag subtree"),
+            ("A", "Clear tags"),
+        ],
+    ),
+    (
+        "Actions",
+        &[
+            ("d", "Send a signal to the tagged or selected processes"),
+            ("p", "Kill by PID"),
+            ("P", "Kill by name, regex or query"),
+            ("L", "Find the processes holding a port"),
+            ("z", "Suspend / resume"),
+            ("+/-/R", "Nice +1 / -1 / set"),
+            ("C", "CPU affinity of the selected process"),
+            ("I", "I/O priority"),
+            ("Y", "Scheduler policy"),
+        ],
+    ),
+    (
+        "Kill dialog",
+        &[
+            ("↑/↓/PgUp/PgDn", "Choose signal"),
+            ("0-9 a-z", "Type a signal name or number"),
+            ("Tab", "Scope: selected, tree, group or session"),
+            ("Enter", "Send"),
+            ("Ctrl+E", "SIGTERM, then SIGKILL after a countdown"),
+            ("Tab", "In kill by pattern: name, regex or query"),
+        ],
+    ),
+    (
+        "Find by port",
+        &[
+            ("j/k", "Choose process"),
+            ("Enter", "Jump to its row"),
+            ("i", "Details"),
+            ("d", "Send a signal"),
+        ],
+    ),
+    (
+        "CPU affinity",
+        &[
+            ("h/j/k/l", "Move in the grid"),
+            ("Space", "Toggle CPU"),
+            ("a", "All / none"),
+            ("t", "Main thread or all threads"),
+            ("Enter", "Apply"),
+        ],
+    ),
+    (
+        "I/O priority and scheduler policy",
+        &[
+            ("Tab", "Class / policy"),
+            ("j/k", "Level / real-time priority"),
+            ("PgUp/PgDn", "Real-time priority ±10"),
+            ("s", "Include descendants (I/O priority)"),
+        ],
+    ),
+    (
+        "Details",
+        &[
+            ("Tab/←/→", "Switch tab"),
+            ("j/k/PgUp/PgDn", "Scroll"),
+            ("/", "Search"),
+            ("m", "Mask secrets"),
+            ("Esc", "Close"),
+        ],
+    ),
+];
+
+/// Message shown in the footer after an action.
+#[derive(Debug)]
+struct Status {
+    text: String,
+    error: bool,
+    shown_at: Instant,
+}
+
+/// Processes that were sent a terminating signal and should be gone by `due`.
+#[derive(Debug)]
+struct ExitCheck {
+    /// PID, name and start time of each target.
+    targets: Vec<(sysinfo::Pid, String, Option<u64>)>,
+    signal: i32,
+    due: Instant,
+}
 
 #[derive(Debug, Default)]
 pub struct App {
     running: bool,
     system: sysinfo::System,
     cpu: Vec<(f64, f64)>,
+    /// Values read from `/proc` for the table, refreshed together with the processes.
+    info: HashMap<sysinfo::Pid, procfs::Info>,
+    /// Whether the socket ports in `info` are filled in since the last refresh.
+    ports_loaded: bool,
+    /// Whether the `smaps_rollup` totals in `info` are filled in since the last refresh.
+    memory_loaded: bool,
     table_state: TableState,
     textarea: TextArea<'static>,
     search: bool,
+    search_full: bool,
+    find: bool,
+    find_textarea: TextArea<'static>,
     kill_modal: bool,
-    kill_pid: Option<sysinfo::Pid>,
+    kill_targets: Vec<Target>,
+    /// Processes the kill modal was opened for, before `kill_scope` is applied.
+    kill_picked: Vec<(sysinfo::Pid, u64)>,
+    /// Whether `kill_picked` came from the tags, which are cleared once a signal is sent.
+    kill_picked_tags: bool,
+    kill_scope: Scope,
+    /// When the SIGKILL of a running TERM-then-KILL escalation is due.
+    escalation: Option<Instant>,
+    protection: Protection,
+    protect_confirm: Option<Confirm>,
+    protect_input: String,
+    /// A change to a single protected process, confirmed by typing its name into `protect_input`.
+    change_confirm: Option<(Change, sysinfo::Pid, u64)>,
+    audit_path: Option<PathBuf>,
+    history_modal: bool,
+    /// Audit log entries, newest first.
+    history: Vec<audit::Entry>,
+    history_selected: usize,
+    help_modal: bool,
+    help_scroll: u16,
+    renice_modal: bool,
+    renice_input: String,
+    /// Process the affinity dialog edits, with the CPUs it may run on.
+    affinity_target: Option<(sysinfo::Pid, u64)>,
+    affinity_cpus: Vec<bool>,
+    affinity_selected: usize,
+    affinity_all_threads: bool,
+    ionice_modal: bool,
+    ionice_priority: sched::IoPriority,
+    ionice_subtree: bool,
+    policy_modal: bool,
+    policy: sched::Policy,
+    rt_priority: i32,
+    kill_results: Vec<SignalResult>,
+    signal_list: Vec<SignalInfo>,
+    signal_list_state: ListState,
+    signal_input: String,
+    last_signal: i32,
+    status: Option<Status>,
+    exit_checks: Vec<ExitCheck>,
+    /// Tagged PIDs with the start time they had when tagged.
+    tagged: BTreeMap<sysinfo::Pid, u64>,
     kill_by_pid_modal: bool,
     kill_by_pid_input: String,
+    pattern_modal: bool,
+    pattern_input: String,
+    pattern_mode: PatternMode,
+    port_modal: bool,
+    port_input: String,
+    /// Processes with a socket on the typed port, looked up whenever the input changes.
+    port_owners: Vec<(sysinfo::Pid, net::Socket)>,
+    port_selected: usize,
     process_list_area: Rect,
-    details_panel: bool,
+    /// Process the details panel shows, followed by PID and start time rather than by row.
+    details_target: Option<(sysinfo::Pid, u64)>,
+    details_tab: details::Tab,
+    details_scroll: u16,
+    /// The current details tab, re-read with every refresh.
+    details_page: details::Page,
+    /// Case-insensitive filter over the rows of the details tab.
+    details_search: String,
+    details_search_input: bool,
+    mask_secrets: bool,
+    memory_metric: MemoryMetric,
+    config: Config,
+    sort_key: SortKey,
+    sort_reverse: bool,
+    columns: Vec<Column>,
+    column_picker: bool,
+    column_selected: usize,
+    preset_picker: bool,
+    preset_selected: usize,
+    preset_save_modal: bool,
+    preset_name_input: String,
+    user_filter: Option<u32>,
+    hide_kernel_threads: bool,
+    only_stopped: bool,
+    user_picker: bool,
+    user_picker_entries: Vec<(u32, String)>,
+    user_selected: usize,
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(config: Config, protection: Protection) -> Self {
         Self {
             running: true,
             system: sysinfo::System::new_all(),
             cpu: vec![],
+            info: HashMap::new(),
+            ports_loaded: false,
+            memory_loaded: false,
             table_state: TableState::default(),
             textarea: {
                 let mut textarea = TextArea::default();
-                // textarea.set_block(Block::bordered().title("Search"));
+                textarea.set_block(search_block(false));
+                textarea
+            },
+            search: false,
+            search_full: false,
+            find: false,
+            find_textarea: {
+                let mut textarea = TextArea::default();
                 textarea.set_block(
-                    Block::default()
-                        .borders(ratatui::widgets::Borders::ALL)
-                        .title("Search (active)")
-                        .style(Style::default().fg(Color::Cyan)),
+                    Block::bordered()
+                        .title("Find [Enter] Keep  [Esc] Clear  [n/N] Next/Prev")
+                        .style(Style::default().fg(Color::Magenta)),
                 );
                 textarea
             },
-            search: false,
             kill_modal: false,
-            kill_pid: None,
+            kill_targets: vec![],
+            kill_picked: vec![],
+            kill_picked_tags: false,
+            kill_scope: Scope::default(),
+            escalation: None,
+            protection,
+            protect_confirm: None,
+            protect_input: String::new(),
+            change_confirm: None,
+            audit_path: config.audit_log.clone().or_else(audit::default_path),
+            history_modal: false,
+            history: vec![],
+            history_selected: 0,
+            help_modal: false,
+            help_scroll: 0,
+            renice_modal: false,
+            renice_input: String::new(),
+            affinity_target: None,
+            affinity_cpus: vec![],
+            affinity_selected: 0,
+            affinity_all_threads: true,
+            ionice_modal: false,
+            ionice_priority: sched::IoPriority::default(),
+            ionice_subtree: false,
+            policy_modal: false,
+            policy: sched::Policy::default(),
+            rt_priority: 0,
+            kill_results: vec![],
+            signal_list: signals::all(),
+            signal_list_state: ListState::default(),
+            signal_input: String::new(),
+            last_signal: libc::SIGTERM,
+            status: None,
+            exit_checks: vec![],
+            tagged: BTreeMap::new(),
             kill_by_pid_modal: false,
             kill_by_pid_input: String::new(),
+            pattern_modal: false,
+            pattern_input: String::new(),
+            pattern_mode: PatternMode::default(),
+            port_modal: false,
+            port_input: String::new(),
+            port_owners: vec![],
+            port_selected: 0,
             process_list_area: Rect::default(),
-            details_panel: false,
+            details_target: None,
+            details_tab: details::Tab::default(),
+            details_scroll: 0,
+            details_page: details::Page::default(),
+            details_search: String::new(),
+            details_search_input: false,
+            mask_secrets: config.mask_secrets,
+            memory_metric: config.memory_metric,
+            config,
+            sort_key: SortKey::default(),
+            sort_reverse: false,
+            columns: Column::default_columns(),
+            column_picker: false,
+            column_selected: 0,
+            preset_picker: false,
+            preset_selected: 0,
+            preset_save_modal: false,
+            preset_name_input: String::new(),
+            user_filter: None,
+            hide_kernel_threads: false,
+            only_stopped: false,
+            user_picker: false,
+            user_picker_entries: vec![],
+            user_selected: 0,
         }
     }
 
@@ -64,7 +418,51 @@ impl App {
         while self.running {
             terminal.draw(|frame| {
                 if frame.count() % 60 == 0 {
-                    self.system.refresh_processes(ProcessesToUpdate::All, true);
+                    // cmd/exe/cwd are needed for full search, not only for the details panel
+                    self.system.refresh_processes_specifics(
+                        ProcessesToUpdate::All,
+                        true,
+                        ProcessRefreshKind::everything(),
+                    );
+                    let processes = self.system.processes();
+                    self.info = processes
+                        .keys()
+                        .map(|pid| (*pid, procfs::info(*pid)))
+                        .collect();
+                    self.ports_loaded = false;
+                    self.memory_loaded = false;
+                    // A tag must not carry over to a new process that got the same PID
+                    self.tagged.retain(|pid, start_time| {
+                        processes
+                            .get(pid)
+                            .is_some_and(|p| p.start_time() == *start_time)
+                    });
+                    self.refresh_details();
+                }
+                // Walking every fd table is only worth it while ports are shown or searched for
+                if !self.ports_loaded && self.needs_ports() {
+                    self.refresh_ports();
+                }
+                // Likewise smaps_rollup, which makes the kernel walk the page tables
+                if !self.memory_loaded && self.memory_metric != MemoryMetric::Rss {
+                    let processes = self.system.processes();
+                    // Threads share the address space of their process, read it once per process
+                    let owner = |pid: &sysinfo::Pid| match processes.get(pid) {
+                        Some(process)
+                            if process.thread_kind() == Some(sysinfo::ThreadKind::Userland) =>
+                        {
+                            process.parent().unwrap_or(*pid)
+                        }
+                        _ => *pid,
+                    };
+                    let mut usage = HashMap::new();
+                    for (pid, info) in self.info.iter_mut() {
+                        info.memory = usage
+                            .entry(owner(pid))
+                            .or_insert_with_key(|owner| procfs::smaps_rollup(*owner).ok())
+                            .clone();
+                    }
+                    self.memory_loaded = true;
                 }
                 self.system.refresh_cpu_all();
                 self.cpu
@@ -72,6 +470,8 @@ impl App {
                 self.draw(frame)
             })?;
             self.handle_crossterm_events()?;
+            self.run_exit_checks();
+            self.run_escalation();
         }
         Ok(())
     }
@@ -147,6 +547,10 @@ impl App {
             self.render_search(frame, third);
         }
 
+        if self.find {
+            self.render_find(frame, third);
+        }
+
         if self.kill_modal {
             self.render_kill_modal(frame, third);
         }
@@ -155,7 +559,59 @@ impl App {
             self.render_kill_by_pid_modal(frame, third);
         }
 
-        if self.details_panel {
+        if self.pattern_modal {
+            self.render_pattern_modal(frame, third);
+        }
+
+        if self.port_modal {
+            self.render_port_modal(frame, third);
+        }
+
+        if self.renice_modal {
+            self.render_renice_modal(frame, third);
+        }
+
+        if self.affinity_target.is_some() {
+            self.render_affinity_modal(frame, third);
+        }
+
+        if self.ionice_modal {
+            self.render_ionice_modal(frame, third);
+        }
+
+        if self.policy_modal {
+            self.render_policy_modal(frame, third);
+        }
+
+        if self.change_confirm.is_some() {
+            self.render_change_confirm(frame, third);
+        }
+
+        if self.column_picker {
+            self.render_column_picker(frame, third);
+        }
+
+        if self.preset_picker {
+            self.render_preset_picker(frame, third);
+        }
+
+        if self.preset_save_modal {
+            self.render_preset_save_modal(frame, third);
+        }
+
+        if self.user_picker {
+            self.render_user_picker(frame, third);
+        }
+
+        if self.history_modal {
+            self.render_history(frame);
+        }
+
+        if self.help_modal {
+            self.render_help(frame);
+        }
+
+        if self.details_target.is_some() {
             self.render_details_panel(frame);
         }
 
@@ -168,19 +624,15 @@ impl App {
     fn render_process_details(&mut self, frame: &mut Frame<'_>, area: Rect) {
         // Show details of the selected process
         let mut text = String::from("No process selected");
-        if let Some(selected) = self.table_state.selected() {
-            let processes: Vec<_> = self.system.processes().iter().collect();
-            if selected < processes.len() {
-                let (_pid, process) = processes[selected];
-                text = format!(
-                    "PID: {}\nName: {:?}\nCPU: {:.2}%\nMemory: {:.2} MB\nStatus: {:?}",
-                    _pid,
-                    process.name(),
-                    process.cpu_usage(),
-                    process.memory() as f64 / 1024.0 / 1024.0,
-                    process.status()
-                );
-            }
+        if let Some(process) = self.selected_process() {
+            text = format!(
+                "PID: {}\nName: {:?}\nCPU: {:.2}%\nMemory: {:.2} MB\nStatus: {:?}",
+                process.pid(),
+                process.name(),
+                process.cpu_usage(),
+                process.memory() as f64 / 1024.0 / 1024.0,
+                process.status()
+            );
         }
         let paragraph = ratatui::widgets::Paragraph::new(text)
             .block(Block::bordered().title("Process Details"));
@@ -189,68 +641,250 @@ impl App {
 
     fn render_footer(&self, frame: &mut Frame<'_>, area: Rect) {
         use ratatui::widgets::Paragraph;
-        let help =
-            "[q/Esc] Quit  [s] Toggle Search  [j/k] Move  [d] Kill  [p] Kill by PID  [Enter] Details  [In Search: Esc] Exit Search  [In Details: Esc] Close";
+        if let Some(status) = self
+            .status
+            .as_ref()
+            .filter(|status| status.shown_at.elapsed() < STATUS_TIMEOUT)
+        {
+            let color = if status.error {
+                Color::Red
+            } else {
+                Color::Green
+            };
+            let paragraph = Paragraph::new(status.text.as_str())
+                .style(Style::default().fg(color))
+                .block(Block::bordered().title("Status"));
+            frame.render_widget(paragraph, area);
+            return;
+        }
+        let help = "[q] Quit  [s] Search  [/] Find  [j/k] Move  [Space] Tag  [d] Kill  [Enter] Details  [?] Help";
         let paragraph = Paragraph::new(help).block(Block::bordered().title("Help"));
         frame.render_widget(paragraph, area);
     }
 
     fn render_processes(&mut self, frame: &mut Frame<'_>, area: Rect) {
+        // Whatever is left after borders, highlight symbol and the fixed columns goes to Command
+        let fixed: u16 = self.columns.iter().filter_map(|c| c.width()).sum();
+        let spacing = self.columns.len().saturating_sub(1) as u16;
+        let cmd_width = area.width.saturating_sub(2 + 2 + fixed + spacing) as usize;
+        let find_query = self.find_query();
         let mut rows: Vec<_> = vec![];
-        for (pid, process) in self.system.processes() {
-            let name = process.name().to_string_lossy().to_string();
-            let user = process
-                .user_id()
-                .and_then(|uid| get_user_by_uid(**uid))
-                .map(|u| u.name().to_string_lossy().to_string())
-                .unwrap_or_default();
-            let cpu = format!("{:.1}%", process.cpu_usage());
-            let mem_mb = format!("{:.1}", process.memory() as f64 / 1024.0 / 1024.0);
-            let row = vec![pid.to_string(), name, user, cpu, mem_mb];
+        for process in self.visible_processes() {
+            let row: Vec<String> = self
+                .columns
+                .iter()
+                .map(|column| {
+                    column.cell(
+                        process,
+                        self.info.get(&process.pid()),
+                        self.memory_metric,
+                        cmd_width,
+                    )
+                })
+                .collect();
             // Create a row with appropriate styling based on process status
             let style = match process.status() {
                 sysinfo::ProcessStatus::Run => Style::default().fg(Color::Green),
                 sysinfo::ProcessStatus::Sleep => Style::default().fg(Color::Yellow),
                 sysinfo::ProcessStatus::Zombie => Style::default().fg(Color::Red),
+                sysinfo::ProcessStatus::Stop => Style::default().fg(Color::LightBlue).italic(),
                 _ => Style::default(),
             };
+            let style = if self.tagged.contains_key(&process.pid()) {
+                style.fg(Color::Cyan).bold()
+            } else {
+                style
+            };
+            // Find keeps every row visible and only marks the matches
+            let style = if !find_query.is_empty()
+                && find_query.matches(process, self.info.get(&process.pid()), self.search_full)
+            {
+                style.bg(Color::Magenta).bold()
+            } else {
+                style
+            };
             rows.push((row, style));
         }
+        // Rendering an empty table clears the selection, pick the first row again once it is back
+        if self.table_state.selected().is_none() && !rows.is_empty() {
+            self.table_state.select(Some(0));
+        }
 
-        rows.sort_by(|a, b| {
-            let a_cpu = a.0[3].replace('%', "").parse::<f32>().unwrap_or(0.0);
-            let b_cpu = b.0[3].replace('%', "").parse::<f32>().unwrap_or(0.0);
-            b_cpu.partial_cmp(&a_cpu).unwrap()
-        });
-
-        let text = self.textarea.lines().first().unwrap();
-        rows.retain(|(row, _)| {
-            row.iter()
-                .any(|cell| cell.to_lowercase().contains(&text.to_lowercase()))
-        });
-
+        let arrow = if self.sort_reverse { "▲" } else { "▼" };
+        let mut title = format!("Processes (sort: {} {})", self.sort_key.label(), arrow);
+        if let Some(uid) = self.user_filter {
+            let name = get_user_by_uid(uid)
+                .map(|u| u.name().to_string_lossy().to_string())
+                .unwrap_or_else(|| uid.to_string());
+            title.push_str(&format!(" [user: {}]", name));
+        }
+        if self.hide_kernel_threads {
+            title.push_str(" [no kernel threads]");
+        }
+        if self.only_stopped {
+            title.push_str(" [stopped only]");
+        } else {
+            // Keep frozen processes from being forgotten
+            let stopped = self
+                .system
+                .processes()
+                .values()
+                .filter(|process| process.status() == sysinfo::ProcessStatus::Stop)
+                .count();
+            if stopped > 0 {
+                title.push_str(&format!(" [{} stopped]", stopped));
+            }
+        }
+        if !self.tagged.is_empty() {
+            title.push_str(&format!(" [{} tagged]", self.tagged.len()));
+        }
         let table = Table::new(
             rows.into_iter()
                 .map(|(row, style)| Row::new(row).style(style))
                 .collect::<Vec<Row>>(),
-            [
-                Constraint::Max(10),
-                Constraint::Fill(1),
-                Constraint::Max(10),
-                Constraint::Max(8),
-                Constraint::Max(10),
-            ],
+            self.columns.iter().map(|column| column.constraint()),
         )
         .row_highlight_style(Style::default().bg(Color::DarkGray))
         .highlight_symbol(">>")
-        .block(Block::bordered().title("Processes"))
+        .block(Block::bordered().title(title))
         .header(
-            Row::new(vec!["PID", "Name", "User", "CPU%", "MemMB"]).style(Style::default().bold()),
+            Row::new(
+                self.columns
+                    .iter()
+                    .map(|column| column.header(self.memory_metric)),
+            )
+            .style(Style::default().bold()),
         );
 
         frame.render_stateful_widget(table, area, &mut self.table_state);
     }
 
+    /// Processes as shown in the table: filtered by the search query and sorted by the sort key.
+    fn visible_processes(&self) -> Vec<&sysinfo::Process> {
+        let query = Query::parse(self.textarea.lines().first().unwrap());
+        let mut processes: Vec<_> = self
+            .system
+            .processes()
+            .values()
+            .filter(|process| {
+                self.user_filter
+                    .is_none_or(|uid| process.user_id().map(|u| **u) == Some(uid))
+            })
+            .filter(|process| !(self.hide_kernel_threads && is_kernel_thread(process)))
+            .filter(|process| {
+                !self.only_stopped || process.status() == sysinfo::ProcessStatus::Stop
+            })
+            .filter(|process| {
+                query.matches(process, self.info.get(&process.pid()), self.search_full)
+            })
+            .collect();
+        processes.sort_by(|a, b| {
+            let order = self.sort_key.compare(a, b, |process| {
+                columns::memory(process, self.info.get(&process.pid()), self.memory_metric)
+            });
+            if self.sort_reverse {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+        processes
+    }
+
+    fn selected_process(&self) -> Option<&sysinfo::Process> {
+        let selected = self.table_state.selected()?;
+        self.visible_processes().get(selected).copied()
+    }
+
+    fn find_query(&self) -> Query {
+        Query::parse(self.find_textarea.lines().first().unwrap())
+    }
+
+    /// Selects the row of `pid`, unless the current filters hide it.
+    fn select_pid(&mut self, pid: sysinfo::Pid) {
+        match self.visible_processes().iter().position(|p| p.pid() == pid) {
+            Some(row) => self.table_state.select(Some(row)),
+            None => self.set_status(
+                format!("PID {} is hidden by the current filters", pid),
+                true,
+            ),
+        }
+    }
+
+    fn look_up_port(&mut self) {
+        self.port_owners = match self.port_input.parse() {
+            Ok(port) => net::port_owners(port),
+            Err(_) => vec![],
+        };
+        self.port_selected = 0;
+    }
+
+    /// Whether the socket ports in `info` are needed, by the Ports column or a `port:` term.
+    fn needs_ports(&self) -> bool {
+        let search = Query::parse(self.textarea.lines().first().unwrap());
+        let pattern = self.pattern_mode == PatternMode::Query
+            && Query::parse(&self.pattern_input).uses_ports();
+        self.columns.contains(&Column::Ports)
+            || search.uses_ports()
+            || self.find_query().uses_ports()
+            || pattern
+    }
+
+    fn refresh_ports(&mut self) {
+        let sockets = net::inet_sockets();
+        for (pid, info) in self.info.iter_mut() {
+            (info.local_ports, info.listening_ports) = net::ports_of(*pid, &sockets);
+        }
+        self.ports_loaded = true;
+    }
+
+    /// Moves the selection to the next (or previous) row matching the find query,
+    /// wrapping around the table. With `include_current` the selected row itself may match,
+    /// which keeps the selection in place while the query is being typed.
+    fn jump_to_match(&mut self, forward: bool, include_current: bool) {
+        let query = self.find_query();
+        if query.is_empty() {
+            return;
+        }
+        let visible = self.visible_processes();
+        let len = visible.len();
+        if len == 0 {
+            return;
+        }
+        let current = self.table_state.selected().unwrap_or(0).min(len - 1);
+        let start = if include_current { 0 } else { 1 };
+        let found = (start..=len)
+            .map(|offset| {
+                if forward {
+                    (current + offset) % len
+                } else {
+                    (current + len * 2 - offset) % len
+                }
+            })
+            .find(|&i| {
+                let info = self.info.get(&visible[i].pid());
+                query.matches(visible[i], info, self.search_full)
+            });
+        if let Some(index) = found {
+            self.table_state.select(Some(index));
+        }
+    }
+
+    fn render_find(&mut self, frame: &mut Frame<'_>, area: Rect) {
+        let find_area = Rect {
+            x: area.x + 1,
+            y: (area.y + area.height).saturating_sub(4),
+            width: area.width - 2,
+            height: 3,
+        };
+        frame.render_widget(Clear, find_area);
+        frame.render_widget(&self.find_textarea, find_area);
+    }
+
+    fn update_search_block(&mut self) {
+        self.textarea.set_block(search_block(self.search_full));
+    }
+
     fn render_search(&mut self, frame: &mut Frame<'_>, area: Rect) {
         let search_area = Rect {
             x: area.x + 1,
@@ -262,314 +896,2627 @@ impl App {
         frame.render_widget(&self.textarea, search_area);
     }
 
-    fn render_kill_modal(&self, frame: &mut Frame<'_>, area: Rect) {
+    fn render_kill_modal(&mut self, frame: &mut Frame<'_>, area: Rect) {
         use ratatui::widgets::Paragraph;
-        let text =
-            "Select signal to send:\n[1] SIGTERM (graceful)\n[2] SIGKILL (force)\n[Esc] Cancel";
         let modal_area = Rect {
             x: area.x + area.width / 4,
-            y: area.y + area.height / 4,
+            y: area.y + 1,
             width: area.width / 2,
-            height: 7,
+            height: area.height.saturating_sub(2).max(7),
         };
         frame.render_widget(Clear, modal_area);
-        let paragraph = Paragraph::new(text).block(Block::bordered().title("Kill process"));
-        frame.render_widget(paragraph, modal_area);
-    }
-
-    fn render_kill_by_pid_modal(&self, frame: &mut Frame<'_>, area: Rect) {
-        use ratatui::widgets::Paragraph;
-        let text = format!(
-            "Enter PID to kill with SIGKILL:\n[{}]\n[Enter] Kill   [Esc] Cancel",
-            self.kill_by_pid_input
-        );
-        let modal_area = Rect {
-            x: area.x + area.width / 4,
-            y: area.y + area.height / 4,
-            width: area.width / 2,
-            height: 6,
+        let title = if self.kill_targets.len() > 1 || self.kill_scope != Scope::Picked {
+            "Kill processes"
+        } else {
+            "Kill process"
         };
-        frame.render_widget(Clear, modal_area);
-        let paragraph = Paragraph::new(text).block(Block::bordered().title("Kill by PID"));
-        frame.render_widget(paragraph, modal_area);
-    }
-
-    fn render_details_panel(&self, frame: &mut Frame) {
-        if let Some(selected) = self.table_state.selected() {
-            let processes: Vec<_> = self.system.processes().iter().collect();
-            if selected < processes.len() {
-                let (pid, process) = processes[selected];
-
-                // Get detailed process information
-                let exe = process
-                    .exe()
-                    .map(|p| format!("{:?}", p))
-                    .unwrap_or_else(|| "Unknown".to_string());
-                let cmd = process
-                    .cmd()
-                    .iter()
-                    .map(|s| s.to_string_lossy())
-                    .collect::<Vec<_>>()
-                    .join(" ");
-                let cwd = process
-                    .cwd()
-                    .map(|p| format!("{:?}", p))
-                    .unwrap_or_else(|| "Unknown".to_string());
-                let disk_usage = process.disk_usage();
-                let memory = process.memory();
-                let virtual_memory = process.virtual_memory();
-                let start_time = format!("{:?}", process.start_time());
-                let run_time = format!("{:?}", process.run_time());
-                let status = format!("{:?}", process.status());
-
-                let details = format!(
-                    "Process Details for PID {}\n\n\
-                    Executable: {}\n\
-                    Command: {}\n\
-                    Working Directory: {}\n\
-                    Status: {}\n\
-                    Start Time: {}\n\
-                    Run Time: {}s\n\n\
-                    Memory Usage:\n\
-                    - Physical: {:.2} MB\n\
-                    - Virtual: {:.2} MB\n\
-                    - Read: {:.2} MB\n\
-                    - Written: {:.2} MB",
-                    pid,
-                    exe,
-                    cmd,
-                    cwd,
-                    status,
-                    start_time,
-                    run_time,
-                    memory as f64 / 1024.0 / 1024.0,
-                    virtual_memory as f64 / 1024.0 / 1024.0,
-                    disk_usage.read_bytes as f64 / 1024.0 / 1024.0,
-                    disk_usage.written_bytes as f64 / 1024.0 / 1024.0
-                );
+        let block = Block::bordered().title(title);
+        let inner = block.inner(modal_area);
+        frame.render_widget(block, modal_area);
 
-                // Create a panel that takes up 80% of the screen width and height
-                let panel_width = (frame.area().width as f32 * 0.8) as u16;
-                let panel_height = (frame.area().height as f32 * 0.8) as u16;
-                let panel_x = (frame.area().width - panel_width) / 2;
-                let panel_y = (frame.area().height - panel_height) / 2;
-
-                let panel_area = Rect::new(panel_x, panel_y, panel_width, panel_height);
+        if let (Some(action), Some((pid, name))) = (self.protect_confirm, self.protected_target()) {
+            let action = match action {
+                Confirm::Signal(number) => format!("send {}", signals::name(number)),
+                Confirm::Escalate => "send SIGTERM, then SIGKILL".to_string(),
+            };
+            let text = format!(
+                "PID {} ({}) is protected.\nType its name to {}: [{}]\n[Enter] Confirm  [Esc] Back",
+                pid, name, action, self.protect_input
+            );
+            frame.render_widget(Paragraph::new(text), inner);
+            return;
+        }
 
-                // Clear the area and render the panel
-                frame.render_widget(Clear, panel_area);
-                let paragraph = ratatui::widgets::Paragraph::new(details)
-                    .block(Block::bordered().title("Process Details (Press Esc to close)"));
-                frame.render_widget(paragraph, panel_area);
+        if let Some(due) = self.escalation {
+            let alive: Vec<_> = self
+                .kill_targets
+                .iter()
+                .filter(|target| self.is_alive(target.pid, target.start_time))
+                .collect();
+            let left = due.saturating_duration_since(Instant::now());
+            let mut lines = vec![
+                format!(
+                    "Sent SIGTERM, {} of {} processes still running",
+                    alive.len(),
+                    self.kill_targets.len()
+                ),
+                format!("SIGKILL in {}s   [Esc] Cancel", left.as_secs_f32().ceil()),
+                String::new(),
+            ];
+            for target in alive {
+                let name = self
+                    .system
+                    .process(target.pid)
+                    .map(|p| p.name().to_string_lossy().to_string())
+                    .unwrap_or_default();
+                lines.push(format!("  {:<8} {}", target.pid.to_string(), name));
             }
+            frame.render_widget(Paragraph::new(lines.join("\n")), inner);
+            return;
         }
-    }
 
-    fn handle_crossterm_events(&mut self) -> Result<()> {
-        if event::poll(std::time::Duration::from_millis(16))? {
-            match event::read()? {
-                Event::Key(key) if key.kind == KeyEventKind::Press => self.on_key_event(key),
-                Event::Mouse(mouse) => self.on_mouse_event(mouse),
-                Event::Resize(_, _) => {}
-                _ => {}
+        if !self.kill_results.is_empty() {
+            let failed = self
+                .kill_results
+                .iter()
+                .filter(|r| r.result.is_err())
+                .count();
+            let mut lines = vec![
+                format!(
+                    "Sent {} to {} {}, {} failed   [Esc] Close",
+                    signals::name(self.last_signal),
+                    self.kill_results.len(),
+                    if self.kill_scope == Scope::Group {
+                        "process groups"
+                    } else {
+                        "processes"
+                    },
+                    failed
+                ),
+                String::new(),
+            ];
+            for result in &self.kill_results {
+                let outcome = match &result.result {
+                    Ok(()) => "ok".to_string(),
+                    Err(err) => format!("failed: {}", err),
+                };
+                lines.push(format!(
+                    "  {:<8} {:<20} {}",
+                    result.pid.to_string(),
+                    result.name,
+                    outcome
+                ));
             }
+            frame.render_widget(Paragraph::new(lines.join("\n")), inner);
+            return;
         }
-        Ok(())
+
+        // Always name the targets, even a single one, before anything is sent
+        let targets_height = (self.kill_targets.len() as u16 + 2)
+            .min(inner.height / 3)
+            .max(3);
+        let [header, list, targets] = Layout::vertical([
+            Constraint::Length(5),
+            Constraint::Fill(1),
+            Constraint::Length(targets_height),
+        ])
+        .areas(inner);
+
+        let hint = if self.chosen_signal().is_none() {
+            "  (unknown signal)"
+        } else {
+            ""
+        };
+        let header_text = format!(
+            "Signal (name or number): [{}]{}\nScope: {}\n[↑/↓] Choose  [Tab] Scope  [Enter] Send  [Esc] Cancel\n[Ctrl+E] SIGTERM, then SIGKILL after {}s",
+            self.signal_input,
+            hint,
+            self.kill_scope.label(),
+            self.config.escalation_timeout
+        );
+        frame.render_widget(Paragraph::new(header_text), header);
+
+        let items: Vec<ListItem> = self
+            .signal_list
+            .iter()
+            .map(|signal| {
+                let last = if signal.number == self.last_signal {
+                    " (last used)"
+                } else {
+                    ""
+                };
+                ListItem::new(format!(
+                    "{:>2} SIG{:<7} {}{}",
+                    signal.number, signal.name, signal.description, last
+                ))
+            })
+            .collect();
+        let signal_list = List::new(items)
+            .highlight_style(Style::default().bg(Color::DarkGray))
+            .highlight_symbol(">>");
+        frame.render_stateful_widget(signal_list, list, &mut self.signal_list_state);
+
+        let order = match self.kill_scope {
+            Scope::TreeLeavesFirst | Scope::TreeParentsFirst => ", in signal order",
+            _ => "",
+        };
+        let mut lines = vec![format!("Targets ({}{}):", self.kill_targets.len(), order)];
+        let cmd_width = (targets.width as usize).saturating_sub(2 + 8 + 1 + 16 + 1);
+        for target in &self.kill_targets {
+            let pid = target.pid;
+            let (name, cmd) = self
+                .system
+                .process(pid)
+                .map(|p| (p.name().to_string_lossy().to_string(), process_cmd(p)))
+                .unwrap_or_default();
+            lines.push(format!(
+                "  {:<8} {:<16} {}",
+                pid.to_string(),
+                name,
+                columns::truncate_middle(&cmd, cmd_width)
+            ));
+        }
+        let paragraph = Paragraph::new(lines.join("\n"))
+            .block(Block::default().borders(ratatui::widgets::Borders::TOP));
+        frame.render_widget(paragraph, targets);
     }
 
-    fn on_key_event(&mut self, key: KeyEvent) {
-        if self.details_panel {
-            if key.code == KeyCode::Esc {
-                self.details_panel = false;
+    fn render_kill_by_pid_modal(&self, frame: &mut Frame<'_>, area: Rect) {
+        use ratatui::widgets::Paragraph;
+        let mut lines = vec![
+            "Enter PIDs (e.g. 1234 1300-1310, 1400):".to_string(),
+            format!("[{}]", self.kill_by_pid_input),
+            "[Enter] Choose signal   [Esc] Cancel".to_string(),
+            String::new(),
+        ];
+        match parse_pid_list(&self.kill_by_pid_input) {
+            Ok(pids) => {
+                for pid in pids {
+                    let line = match self.system.process(pid) {
+                        Some(process) => format!(
+                            "{:<8} {:<20} {:<10} {}",
+                            pid.to_string(),
+                            process.name().to_string_lossy(),
+                            process_user(process),
+                            process_cmd(process)
+                        ),
+                        None => format!("{:<8} no such process", pid.to_string()),
+                    };
+                    lines.push(line);
+                }
             }
-            return;
+            Err(err) => lines.push(err),
         }
+        let modal_area = Rect {
+            x: area.x + area.width / 6,
+            y: area.y + 1,
+            width: area.width * 2 / 3,
+            height: (lines.len() as u16 + 2).min(area.height.saturating_sub(2)),
+        };
+        frame.render_widget(Clear, modal_area);
+        let paragraph =
+            Paragraph::new(lines.join("\n")).block(Block::bordered().title("Kill by PID"));
+        frame.render_widget(paragraph, modal_area);
+    }
 
-        if self.kill_by_pid_modal {
-            match key.code {
-                KeyCode::Esc => {
-                    self.kill_by_pid_modal = false;
-                    self.kill_by_pid_input.clear();
-                }
-                KeyCode::Enter => {
-                    self.try_kill_by_pid();
-                    self.kill_by_pid_modal = false;
-                    self.kill_by_pid_input.clear();
-                }
-                KeyCode::Char(c) if c.is_ascii_digit() => {
-                    self.kill_by_pid_input.push(c);
+    fn render_pattern_modal(&self, frame: &mut Frame<'_>, area: Rect) {
+        use ratatui::widgets::Paragraph;
+        let mut lines = vec![
+            format!(
+                "Pattern ({}): [{}]",
+                self.pattern_mode.label(),
+                self.pattern_input
+            ),
+            "[Tab] Change mode   [Enter] Choose signal   [Esc] Cancel".to_string(),
+            String::new(),
+        ];
+        let mut title = "Kill by pattern".to_string();
+        match self.pattern_matches() {
+            Ok(matches) => {
+                title = format!("Kill by pattern ({} matching)", matches.len());
+                // Per-name counts first, so a too broad pattern stands out immediately
+                let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+                for process in &matches {
+                    *counts
+                        .entry(process.name().to_string_lossy().to_string())
+                        .or_default() += 1;
                 }
-                KeyCode::Backspace => {
-                    self.kill_by_pid_input.pop();
+                if !counts.is_empty() {
+                    let summary: Vec<_> = counts
+                        .iter()
+                        .map(|(name, count)| format!("{} ×{}", name, count))
+                        .collect();
+                    lines.push(summary.join(", "));
+                    lines.push(String::new());
                 }
-                _ => {}
-            }
-            return;
-        }
-        if self.kill_modal {
-            match key.code {
-                KeyCode::Char('1') => self.send_signal(Signal::Term),
-                KeyCode::Char('2') => self.send_signal(Signal::Kill),
-                KeyCode::Esc => {
-                    self.kill_modal = false;
-                    self.kill_pid = None;
+                for process in matches {
+                    lines.push(format!(
+                        "{:<8} {:<20} {:<10} {}",
+                        process.pid().to_string(),
+                        process.name().to_string_lossy(),
+                        process_user(process),
+                        process_cmd(process)
+                    ));
                 }
-                _ => {}
             }
-            return;
+            Err(err) => lines.push(format!("Invalid pattern: {}", err)),
         }
-        if self.search {
-            if key.code == KeyCode::Esc {
-                self.search = false;
-            } else {
-                match key.code {
-                    KeyCode::Enter => {
-                        self.search = false;
-                    }
-                    _ => {
-                        self.textarea.input(key);
-                    }
+        let modal_area = Rect {
+            x: area.x + area.width / 6,
+            y: area.y + 1,
+            width: area.width * 2 / 3,
+            height: (lines.len() as u16 + 2).min(area.height.saturating_sub(2)),
+        };
+        frame.render_widget(Clear, modal_area);
+        let paragraph = Paragraph::new(lines.join("\n")).block(Block::bordered().title(title));
+        frame.render_widget(paragraph, modal_area);
+    }
+
+    fn render_port_modal(&self, frame: &mut Frame<'_>, area: Rect) {
+        use ratatui::widgets::Paragraph;
+        let mut lines = vec![
+            format!("Port: [{}]", self.port_input),
+            "[j/k] Move  [Enter] Go to row  [i] Details  [d] Send signal  [Esc] Close".to_string(),
+            String::new(),
+        ];
+        if self.port_owners.is_empty() {
+            if self.port_input.parse::<u16>().is_err() && !self.port_input.is_empty() {
+                lines.push("Not a valid port number".to_string());
+            } else if !self.port_input.is_empty() {
+                lines.push(format!(
+                    "No process has a TCP or UDP socket on port {}",
+                    self.port_input
+                ));
+                if get_current_uid() != 0 {
+                    lines.push(
+                        "Sockets of other users' processes are only visible to root".to_string(),
+                    );
                 }
             }
-            return;
+        } else {
+            lines.push(format!(
+                "   {:<8} {:<20} {:<10} {:<6} {:<12} Local",
+                "PID", "Name", "User", "Proto", "State"
+            ));
         }
-        match (key.modifiers, key.code) {
-            (_, KeyCode::Esc | KeyCode::Char('q'))
-            | (KeyModifiers::CONTROL, KeyCode::Char('c') | KeyCode::Char('C')) => self.quit(),
-
-            (_, KeyCode::Char('j')) => {
-                self.table_state.select_next();
-            }
-            (_, KeyCode::Char('k')) => {
-                self.table_state.select_previous();
-            }
-            (_, KeyCode::Char('s')) => {
-                self.search = !self.search;
-            }
-            (_, KeyCode::Char('d')) => {
-                self.prepare_kill_modal();
-            }
-            (_, KeyCode::Char('p')) => {
-                self.kill_by_pid_modal = true;
-                self.kill_by_pid_input.clear();
-            }
-            (_, KeyCode::Enter) => {
-                self.details_panel = true;
-            }
-            _ => {}
+        for (i, (pid, socket)) in self.port_owners.iter().enumerate() {
+            let process = self.system.process(*pid);
+            let marker = if i == self.port_selected { ">>" } else { "  " };
+            lines.push(format!(
+                "{} {:<8} {:<20} {:<10} {:<6} {:<12} {}",
+                marker,
+                pid.to_string(),
+                process.map_or(String::new(), |p| p.name().to_string_lossy().to_string()),
+                process.map_or(String::new(), process_user),
+                socket.protocol.label(),
+                socket.state,
+                socket.local_label()
+            ));
         }
+        let modal_area = Rect {
+            x: area.x + area.width / 6,
+            y: area.y + 1,
+            width: area.width * 2 / 3,
+            height: (lines.len() as u16 + 2).min(area.height.saturating_sub(2)),
+        };
+        frame.render_widget(Clear, modal_area);
+        let paragraph =
+            Paragraph::new(lines.join("\n")).block(Block::bordered().title("Find by port"));
+        frame.render_widget(paragraph, modal_area);
     }
 
-    fn on_mouse_event(&mut self, mouse: MouseEvent) {
-        if self.search || self.kill_modal || self.kill_by_pid_modal {
+    fn render_column_picker(&self, frame: &mut Frame<'_>, area: Rect) {
+        use ratatui::widgets::Paragraph;
+        let mut lines = vec![
+            "[j/k] Move  [Space] Toggle  [Esc] Close".to_string(),
+            String::new(),
+        ];
+        for (i, column) in Column::ALL.iter().enumerate() {
+            let marker = if i == self.column_selected {
+                ">>"
+            } else {
+                "  "
+            };
+            let checked = if self.columns.contains(column) {
+                "x"
+            } else {
+                " "
+            };
+            lines.push(format!(
+                "{} [{}] {}",
+                marker,
+                checked,
+                column.header(self.memory_metric)
+            ));
+        }
+        let modal_area = Rect {
+            x: area.x + area.width / 4,
+            y: area.y + 1,
+            width: area.width / 2,
+            height: (lines.len() as u16 + 2).min(area.height.saturating_sub(1)),
+        };
+        frame.render_widget(Clear, modal_area);
+        let paragraph = Paragraph::new(lines.join("\n")).block(Block::bordered().title("Columns"));
+        frame.render_widget(paragraph, modal_area);
+    }
+
+    fn render_preset_picker(&self, frame: &mut Frame<'_>, area: Rect) {
+        use ratatui::widgets::Paragraph;
+        let mut lines = vec![
+            "[j/k] Move  [Enter] Apply  [Del] Remove  [Esc] Close".to_string(),
+            String::new(),
+        ];
+        if self.config.presets.is_empty() {
+            lines.push("No presets yet, press [S] in the table to save the current view".into());
+        }
+        for (i, preset) in self.config.presets.iter().enumerate() {
+            let marker = if i == self.preset_selected {
+                ">>"
+            } else {
+                "  "
+            };
+            let key = if i < 9 {
+                format!("[{}]", i + 1)
+            } else {
+                "   ".to_string()
+            };
+            lines.push(format!(
+                "{} {} {:<20} {}",
+                marker, key, preset.name, preset.query
+            ));
+        }
+        let modal_area = Rect {
+            x: area.x + area.width / 6,
+            y: area.y + 1,
+            width: area.width * 2 / 3,
+            height: (lines.len() as u16 + 2).min(area.height.saturating_sub(1)),
+        };
+        frame.render_widget(Clear, modal_area);
+        let paragraph =
+            Paragraph::new(lines.join("\n")).block(Block::bordered().title("Filter presets"));
+        frame.render_widget(paragraph, modal_area);
+    }
+
+    fn render_renice_modal(&self, frame: &mut Frame<'_>, area: Rect) {
+        use ratatui::widgets::Paragraph;
+        let picked = self.picked_processes();
+        let names: Vec<_> = picked
+            .iter()
+            .filter_map(|(pid, _)| {
+                let process = self.system.process(*pid)?;
+                Some(format!("{} ({})", pid, process.name().to_string_lossy()))
+            })
+            .collect();
+        let text = format!(
+            "Nice value for {}:\n[{}]   (-20 highest priority … 19 lowest)\n[Enter] Apply   [Esc] Cancel",
+            names.join(", "),
+            self.renice_input
+        );
+        let modal_area = Rect {
+            x: area.x + area.width / 4,
+            y: area.y + area.height / 4,
+            width: area.width / 2,
+            height: 6,
+        };
+        frame.render_widget(Clear, modal_area);
+        let paragraph = Paragraph::new(text)
+            .wrap(ratatui::widgets::Wrap { trim: false })
+            .block(Block::bordered().title("Renice"));
+        frame.render_widget(paragraph, modal_area);
+    }
+
+    fn render_change_confirm(&self, frame: &mut Frame<'_>, area: Rect) {
+        use ratatui::widgets::Paragraph;
+        let Some((change, pid, _)) = &self.change_confirm else {
+            return;
+        };
+        let name = self
+            .system
+            .process(*pid)
+            .map(|p| p.name().to_string_lossy().to_string())
+            .unwrap_or_default();
+        let text = format!(
+            "PID {} ({}) is protected.\nType its name to {}: [{}]\n[Enter] Confirm  [Esc] Back",
+            pid,
+            name,
+            change.describe(),
+            self.protect_input
+        );
+        let modal_area = Rect {
+            x: area.x + area.width / 4,
+            y: area.y + area.height / 4,
+            width: area.width / 2,
+            height: 5,
+        };
+        frame.render_widget(Clear, modal_area);
+        let paragraph = Paragraph::new(text)
+            .wrap(ratatui::widgets::Wrap { trim: false })
+            .block(Block::bordered().title("Protected process"));
+        frame.render_widget(paragraph, modal_area);
+    }
+
+    fn render_affinity_modal(&self, frame: &mut Frame<'_>, area: Rect) {
+        use ratatui::widgets::Paragraph;
+        let Some((pid, _)) = self.affinity_target else {
+            return;
+        };
+        let name = self
+            .system
+            .process(pid)
+            .map(|p| p.name().to_string_lossy().to_string())
+            .unwrap_or_default();
+        let chosen: Vec<_> = (0..self.affinity_cpus.len())
+            .filter(|cpu| self.affinity_cpus[*cpu])
+            .collect();
+        let mut lines = vec![
+            format!(
+                "PID {} ({}): CPUs {}",
+                pid,
+                name,
+                sched::format_cpus(&chosen)
+            ),
+            format!(
+                "[{}] Apply to all threads",
+                if self.affinity_all_threads { "x" } else { " " }
+            ),
+            "[hjkl] Move [Space] Toggle [a] All [t] Threads [Enter] Apply [Esc] Cancel".to_string(),
+            String::new(),
+        ];
+        for (row, cpus) in self.affinity_cpus.chunks(AFFINITY_COLUMNS).enumerate() {
+            let cells: Vec<_> = cpus
+                .iter()
+                .enumerate()
+                .map(|(column, checked)| {
+                    let cpu = row * AFFINITY_COLUMNS + column;
+                    let cursor = if cpu == self.affinity_selected {
+                        ">"
+                    } else {
+                        " "
+                    };
+                    let checked = if *checked { "x" } else { " " };
+                    format!("{}[{}] {:<3}", cursor, checked, cpu)
+                })
+                .collect();
+            lines.push(cells.join(" "));
+        }
+        let modal_area = Rect {
+            x: area.x + area.width / 4,
+            y: area.y + 1,
+            width: area.width / 2,
+            height: (lines.len() as u16 + 2).min(area.height.saturating_sub(1)),
+        };
+        frame.render_widget(Clear, modal_area);
+        let paragraph =
+            Paragraph::new(lines.join("\n")).block(Block::bordered().title("CPU affinity"));
+        frame.render_widget(paragraph, modal_area);
+    }
+
+    fn render_ionice_modal(&self, frame: &mut Frame<'_>, area: Rect) {
+        use ratatui::widgets::Paragraph;
+        let targets = self.ionice_targets();
+        let confirm = match self.picked_processes()[..] {
+            [(pid, _)] => Some(pid),
+            _ => None,
+        };
+        let level = if self.ionice_priority.class.has_level() {
+            "   [j/k] Level (0 highest … 7 lowest)"
+        } else {
+            ""
+        };
+        let mut lines = vec![
+            format!("I/O priority: {}", self.ionice_priority),
+            format!(
+                "[{}] Include all descendants",
+                if self.ionice_subtree { "x" } else { " " }
+            ),
+            format!("[Tab] Class{}", level),
+            "[s] Descendants  [Enter] Apply  [Esc] Cancel".to_string(),
+            String::new(),
+            format!("Targets ({}):", targets.len()),
+        ];
+        for (pid, _) in &targets {
+            let name = self
+                .system
+                .process(*pid)
+                .map(|p| p.name().to_string_lossy().to_string())
+                .unwrap_or_default();
+            let current = self
+                .info
+                .get(pid)
+                .and_then(|info| info.io_priority)
+                .map(|priority| priority.to_string())
+                .unwrap_or_default();
+            // A single picked process is confirmed by name instead of being skipped
+            let protected = if confirm != Some(*pid)
+                && self
+                    .system
+                    .process(*pid)
+                    .is_some_and(|process| self.protection.protects(process))
+            {
+                "  (protected, skipped)"
+            } else {
+                ""
+            };
+            lines.push(format!(
+                "  {:<8} {:<20} {}{}",
+                pid.to_string(),
+                name,
+                current,
+                protected
+            ));
+        }
+        let modal_area = Rect {
+            x: area.x + area.width / 4,
+            y: area.y + 1,
+            width: area.width / 2,
+            height: (lines.len() as u16 + 2).min(area.height.saturating_sub(1)),
+        };
+        frame.render_widget(Clear, modal_area);
+        let paragraph =
+            Paragraph::new(lines.join("\n")).block(Block::bordered().title("I/O priority"));
+        frame.render_widget(paragraph, modal_area);
+    }
+
+    fn render_policy_modal(&self, frame: &mut Frame<'_>, area: Rect) {
+        use ratatui::widgets::Paragraph;
+        let picked = self.picked_processes();
+        let mut lines = vec![
+            format!("Policy: {}", self.policy.label()),
+            format!("Real-time priority: {}", self.rt_priority),
+            if self.policy.is_realtime() {
+                "[Tab] Policy  [j/k] Priority ±1  [PgUp/PgDn] ±10  [Enter] Apply  [Esc] Cancel"
+            } else {
+                "[Tab] Policy  [Enter] Apply  [Esc] Cancel"
+            }
+            .to_string(),
+            String::new(),
+            format!("Targets ({}):", picked.len()),
+        ];
+        for (pid, _) in &picked {
+            let name = self
+                .system
+                .process(*pid)
+                .map(|p| p.name().to_string_lossy().to_string())
+                .unwrap_or_default();
+            let info = self.info.get(pid);
+            let current = match (
+                info.and_then(|i| i.policy),
+                info.and_then(|i| i.rt_priority),
+            ) {
+                (Some(policy), Some(priority)) => format!("{} {}", policy.label(), priority),
+                _ => String::new(),
+            };
+            lines.push(format!("  {:<8} {:<20} {}", pid.to_string(), name, current));
+        }
+        let modal_area = Rect {
+            x: area.x + area.width / 4,
+            y: area.y + 1,
+            width: area.width / 2,
+            height: (lines.len() as u16 + 2).min(area.height.saturating_sub(1)),
+        };
+        frame.render_widget(Clear, modal_area);
+        let paragraph =
+            Paragraph::new(lines.join("\n")).block(Block::bordered().title("Scheduler policy"));
+        frame.render_widget(paragraph, modal_area);
+    }
+
+    fn render_preset_save_modal(&self, frame: &mut Frame<'_>, area: Rect) {
+        use ratatui::widgets::Paragraph;
+        let text = format!(
+            "Save current search, sort and columns as:\n[{}]\n[Enter] Save   [Esc] Cancel",
+            self.preset_name_input
+        );
+        let modal_area = Rect {
+            x: area.x + area.width / 4,
+            y: area.y + area.height / 4,
+            width: area.width / 2,
+            height: 6,
+        };
+        frame.render_widget(Clear, modal_area);
+        let paragraph = Paragraph::new(text).block(Block::bordered().title("Save preset"));
+        frame.render_widget(paragraph, modal_area);
+    }
+
+    fn render_user_picker(&self, frame: &mut Frame<'_>, area: Rect) {
+        use ratatui::widgets::Paragraph;
+        let mut lines = vec![
+            "[j/k] Move  [Enter] Show only this user  [Esc] Close".to_string(),
+            String::new(),
+        ];
+        let marker = |selected: bool| if selected { ">>" } else { "  " };
+        lines.push(format!("{} All users", marker(self.user_selected == 0)));
+        for (i, (uid, name)) in self.user_picker_entries.iter().enumerate() {
+            lines.push(format!(
+                "{} {:<20} (uid {})",
+                marker(self.user_selected == i + 1),
+                name,
+                uid
+            ));
+        }
+        // Keep the selected user on screen when the list is taller than the modal
+        let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(1));
+        let scroll = (self.user_selected + 2 + 3).saturating_sub(height as usize) as u16;
+        let modal_area = Rect {
+            x: area.x + area.width / 4,
+            y: area.y + 1,
+            width: area.width / 2,
+            height,
+        };
+        frame.render_widget(Clear, modal_area);
+        let paragraph = Paragraph::new(lines.join("\n"))
+            .scroll((scroll, 0))
+            .block(Block::bordered().title("Filter by user"));
+        frame.render_widget(paragraph, modal_area);
+    }
+
+    fn render_history(&self, frame: &mut Frame) {
+        use ratatui::widgets::Paragraph;
+        let area = frame.area();
+        let panel_area = Rect {
+            x: area.width / 10,
+            y: area.height / 10,
+            width: area.width * 4 / 5,
+            height: area.height * 4 / 5,
+        };
+        frame.render_widget(Clear, panel_area);
+        let block = Block::bordered().title(format!(
+            "History ({} actions) [j/k] Move  [Esc] Close",
+            self.history.len()
+        ));
+        let inner = block.inner(panel_area);
+        frame.render_widget(block, panel_area);
+        let [list, selected] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(4)]).areas(inner);
+
+        let mut lines = vec![format!(
+            "   {:<19} {:<10} {:<8} {:<20} {:<16} Result",
+            "Time", "User", "PID", "Name", "Action"
+        )];
+        for (i, entry) in self.history.iter().enumerate() {
+            let user = get_user_by_uid(entry.uid)
+                .map(|u| u.name().to_string_lossy().to_string())
+                .unwrap_or_else(|| entry.uid.to_string());
+            let marker = if i == self.history_selected {
+                ">>"
+            } else {
+                "  "
+            };
+            lines.push(format!(
+                "{} {:<19} {:<10} {:<8} {:<20} {:<16} {}",
+                marker,
+                audit::format_time(entry.timestamp),
+                user,
+                entry.pid,
+                entry.name,
+                entry.action,
+                entry.result
+            ));
+        }
+        // Keep the selected entry on screen, below the header line
+        let scroll = (self.history_selected + 2).saturating_sub(list.height as usize) as u16;
+        frame.render_widget(Paragraph::new(lines.join("\n")).scroll((scroll, 0)), list);
+
+        let text = match self.history.get(self.history_selected) {
+            Some(entry) => format!(
+                "Started: {}\nCommand: {}",
+                entry
+                    .start_time
+                    .map_or("unknown".to_string(), audit::format_time),
+                entry.cmdline
+            ),
+            None => "No actions recorded yet".to_string(),
+        };
+        let paragraph = Paragraph::new(text)
+            .wrap(ratatui::widgets::Wrap { trim: false })
+            .block(Block::default().borders(ratatui::widgets::Borders::TOP));
+        frame.render_widget(paragraph, selected);
+    }
+
+    fn render_help(&self, frame: &mut Frame) {
+        use ratatui::widgets::Paragraph;
+        let area = frame.area();
+        let panel_area = Rect {
+            x: area.width / 10,
+            y: area.height / 10,
+            width: area.width * 4 / 5,
+            height: area.height * 4 / 5,
+        };
+        frame.render_widget(Clear, panel_area);
+        let mut lines = vec![];
+        for (section, keys) in HELP {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(section.to_string());
+            for (key, action) in *keys {
+                lines.push(format!("  {:<15} {}", key, action));
+            }
+        }
+        let paragraph = Paragraph::new(lines.join("\n"))
+            .scroll((self.help_scroll, 0))
+            .block(Block::bordered().title("Help [j/k] Scroll  [Esc/?] Close"));
+        frame.render_widget(paragraph, panel_area);
+    }
+
+    fn render_details_panel(&mut self, frame: &mut Frame) {
+        use ratatui::widgets::{Borders, Paragraph, Tabs, Wrap};
+
+        // Create a panel that takes up 80% of the screen width and height
+        let panel_width = (frame.area().width as f32 * 0.8) as u16;
+        let panel_height = (frame.area().height as f32 * 0.8) as u16;
+        let panel_x = (frame.area().width - panel_width) / 2;
+        let panel_y = (frame.area().height - panel_height) / 2;
+        let panel_area = Rect::new(panel_x, panel_y, panel_width, panel_height);
+
+        let Some((pid, _)) = self.details_target else {
+            return;
+        };
+        let name = self
+            .system
+            .process(pid)
+            .map(|process| process.name().to_string_lossy().to_string())
+            .unwrap_or_default();
+        frame.render_widget(Clear, panel_area);
+        let block = Block::bordered().title(format!(
+            "Process Details: {} {} [Tab/←/→] Switch  [j/k/PgUp/PgDn] Scroll  [/] Search  [Esc] Close",
+            pid, name
+        ));
+        let inner = block.inner(panel_area);
+        frame.render_widget(block, panel_area);
+        let searching = self.details_search_input || !self.details_search.is_empty();
+        let [tabs_area, content, search_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(if searching { 1 } else { 0 }),
+        ])
+        .areas(inner);
+
+        let tabs = Tabs::new(details::Tab::ALL.map(details::Tab::title))
+            .select(self.details_tab as usize)
+            .highlight_style(Style::default().fg(Color::Cyan).bold())
+            .block(Block::default().borders(Borders::BOTTOM));
+        frame.render_widget(tabs, tabs_area);
+
+        let search = self.details_search.to_lowercase();
+        let rows: Vec<_> = self
+            .details_page
+            .rows
+            .iter()
+            .filter(|row| row.to_lowercase().contains(&search))
+            .collect();
+        if searching {
+            let cursor = if self.details_search_input { "_" } else { "" };
+            let line = format!(
+                "Search: {}{}  ({} of {} rows)",
+                self.details_search,
+                cursor,
+                rows.len(),
+                self.details_page.rows.len()
+            );
+            frame.render_widget(Paragraph::new(line).fg(Color::Magenta), search_area);
+        }
+        let lines: Vec<_> = self.details_page.header.iter().chain(rows).collect();
+
+        // Lines as they will be wrapped, so End and PgDn stop at the last page
+        let width = content.width.max(1) as usize;
+        let wrapped: usize = lines
+            .iter()
+            .map(|line| line.chars().count().div_ceil(width).max(1))
+            .sum();
+        let max_scroll = wrapped.saturating_sub(content.height as usize);
+        self.details_scroll = self.details_scroll.min(max_scroll as u16);
+        let text = lines
+            .iter()
+            .map(|line| line.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let paragraph = Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .scroll((self.details_scroll, 0));
+        frame.render_widget(paragraph, content);
+    }
+
+    /// Opens the details panel for the selected process.
+    fn open_details(&mut self) {
+        if let Some(process) = self.selected_process() {
+            self.open_details_for(process.pid());
+        }
+    }
+
+    fn open_details_for(&mut self, pid: sysinfo::Pid) {
+        let Some(process) = self.system.process(pid) else {
+            return;
+        };
+        self.details_target = Some((pid, process.start_time()));
+        self.details_scroll = 0;
+        self.details_search.clear();
+        self.refresh_details();
+    }
+
+    fn switch_details_tab(&mut self, tab: details::Tab) {
+        self.details_tab = tab;
+        self.details_scroll = 0;
+        self.details_search.clear();
+        self.refresh_details();
+    }
+
+    /// Re-reads the current details tab of the process the panel was opened for.
+    fn refresh_details(&mut self) {
+        let Some((pid, start_time)) = self.details_target else {
+            return;
+        };
+        self.details_page = match self.system.process(pid) {
+            Some(process) if process.start_time() == start_time => {
+                let info = self.info.get(&pid);
+                details::page(self.details_tab, process, info, self.mask_secrets)
+            }
+            _ => details::Page {
+                header: vec![format!("Process {} has exited", pid)],
+                rows: vec![],
+            },
+        };
+    }
+
+    fn handle_crossterm_events(&mut self) -> Result<()> {
+        if event::poll(std::time::Duration::from_millis(16))? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => self.on_key_event(key),
+                Event::Mouse(mouse) => self.on_mouse_event(mouse),
+                Event::Resize(_, _) => {}
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn on_key_event(&mut self, key: KeyEvent) {
+        if self.details_search_input {
+            match key.code {
+                KeyCode::Esc => {
+                    self.details_search_input = false;
+                    self.details_search.clear();
+                }
+                KeyCode::Enter => self.details_search_input = false,
+                KeyCode::Backspace => {
+                    self.details_search.pop();
+                }
+                KeyCode::Char(c) => {
+                    self.details_search.push(c);
+                    self.details_scroll = 0;
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if self.details_target.is_some() {
+            match key.code {
+                KeyCode::Esc if !self.details_search.is_empty() => self.details_search.clear(),
+                KeyCode::Esc => self.details_target = None,
+                KeyCode::Char('/') => self.details_search_input = true,
+                KeyCode::Char('m') => {
+                    self.mask_secrets = !self.mask_secrets;
+                    self.refresh_details();
+                }
+                KeyCode::Tab | KeyCode::Right | KeyCode::Char('l') => {
+                    self.switch_details_tab(self.details_tab.next());
+                }
+                KeyCode::BackTab | KeyCode::Left | KeyCode::Char('h') => {
+                    self.switch_details_tab(self.details_tab.prev());
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.details_scroll = self.details_scroll.saturating_add(1);
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.details_scroll = self.details_scroll.saturating_sub(1);
+                }
+                KeyCode::PageDown => self.details_scroll = self.details_scroll.saturating_add(10),
+                KeyCode::PageUp => self.details_scroll = self.details_scroll.saturating_sub(10),
+                KeyCode::Home | KeyCode::Char('g') => self.details_scroll = 0,
+                // Clamped to the last page when rendering
+                KeyCode::End | KeyCode::Char('G') => self.details_scroll = u16::MAX,
+                _ => {}
+            }
+            return;
+        }
+
+        if self.help_modal {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('?' | 'q') => self.help_modal = false,
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.help_scroll = self.help_scroll.saturating_add(1);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.help_scroll = self.help_scroll.saturating_sub(1);
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if self.kill_by_pid_modal {
+            match key.code {
+                KeyCode::Esc => {
+                    self.kill_by_pid_modal = false;
+                    self.kill_by_pid_input.clear();
+                }
+                KeyCode::Enter if self.prepare_kill_by_pid() => {
+                    self.kill_by_pid_modal = false;
+                    self.kill_by_pid_input.clear();
+                }
+                KeyCode::Char(c) if c.is_ascii_digit() || matches!(c, ' ' | ',' | '-') => {
+                    self.kill_by_pid_input.push(c);
+                }
+                KeyCode::Backspace => {
+                    self.kill_by_pid_input.pop();
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.pattern_modal {
+            match key.code {
+                KeyCode::Esc => {
+                    self.pattern_modal = false;
+                    self.pattern_input.clear();
+                }
+                KeyCode::Tab => self.pattern_mode = self.pattern_mode.next(),
+                KeyCode::Enter if self.prepare_pattern_kill() => {
+                    self.pattern_modal = false;
+                    self.pattern_input.clear();
+                }
+                KeyCode::Char(c) => self.pattern_input.push(c),
+                KeyCode::Backspace => {
+                    self.pattern_input.pop();
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.port_modal {
+            match key.code {
+                KeyCode::Esc => self.port_modal = false,
+                KeyCode::Char(c) if c.is_ascii_digit() => {
+                    self.port_input.push(c);
+                    self.look_up_port();
+                }
+                KeyCode::Backspace => {
+                    self.port_input.pop();
+                    self.look_up_port();
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.port_selected =
+                        (self.port_selected + 1).min(self.port_owners.len().saturating_sub(1));
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.port_selected = self.port_selected.saturating_sub(1);
+                }
+                KeyCode::Enter | KeyCode::Char('i' | 'd') => {
+                    if let Some((pid, _)) = self.port_owners.get(self.port_selected) {
+                        let pid = *pid;
+                        self.port_modal = false;
+                        match key.code {
+                            KeyCode::Enter => self.select_pid(pid),
+                            KeyCode::Char('i') => self.open_details_for(pid),
+                            _ => {
+                                if let Some(process) = self.system.process(pid) {
+                                    self.open_kill_modal(vec![(pid, process.start_time())]);
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.column_picker {
+            match key.code {
+                KeyCode::Esc => self.column_picker = false,
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.column_selected = (self.column_selected + 1).min(Column::ALL.len() - 1);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.column_selected = self.column_selected.saturating_sub(1);
+                }
+                KeyCode::Char(' ') => self.toggle_column(Column::ALL[self.column_selected]),
+                _ => {}
+            }
+            return;
+        }
+        if self.preset_picker {
+            match key.code {
+                KeyCode::Esc => self.preset_picker = false,
+                KeyCode::Char('j') | KeyCode::Down => {
+                    let last = self.config.presets.len().saturating_sub(1);
+                    self.preset_selected = (self.preset_selected + 1).min(last);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.preset_selected = self.preset_selected.saturating_sub(1);
+                }
+                KeyCode::Enter => {
+                    self.apply_preset(self.preset_selected);
+                    self.preset_picker = false;
+                }
+                KeyCode::Delete => self.remove_preset(self.preset_selected),
+                _ => {}
+            }
+            return;
+        }
+        if self.affinity_target.is_some() {
+            let count = self.affinity_cpus.len();
+            match key.code {
+                KeyCode::Esc => self.affinity_target = None,
+                KeyCode::Char('h') | KeyCode::Left => {
+                    self.affinity_selected = self.affinity_selected.saturating_sub(1);
+                }
+                KeyCode::Char('l') | KeyCode::Right => {
+                    self.affinity_selected = (self.affinity_selected + 1).min(count - 1);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.affinity_selected =
+                        self.affinity_selected.saturating_sub(AFFINITY_COLUMNS);
+                }
+                KeyCode::Char('j') | KeyCode::Down
+                    if self.affinity_selected + AFFINITY_COLUMNS < count =>
+                {
+                    self.affinity_selected += AFFINITY_COLUMNS;
+                }
+                KeyCode::Char(' ') => {
+                    self.affinity_cpus[self.affinity_selected] ^= true;
+                }
+                KeyCode::Char('a') => {
+                    let all = self.affinity_cpus.iter().all(|checked| *checked);
+                    self.affinity_cpus.fill(!all);
+                }
+                KeyCode::Char('t') => self.affinity_all_threads = !self.affinity_all_threads,
+                KeyCode::Enter => self.apply_affinity(),
+                _ => {}
+            }
+            return;
+        }
+        if self.policy_modal {
+            match key.code {
+                KeyCode::Esc => self.policy_modal = false,
+                KeyCode::Tab => {
+                    self.policy = self.policy.next();
+                    self.rt_priority = if self.policy.is_realtime() { 1 } else { 0 };
+                }
+                KeyCode::Char('k') | KeyCode::Up => self.change_rt_priority(1),
+                KeyCode::Char('j') | KeyCode::Down => self.change_rt_priority(-1),
+                KeyCode::PageUp => self.change_rt_priority(10),
+                KeyCode::PageDown => self.change_rt_priority(-10),
+                KeyCode::Enter => {
+                    self.policy_modal = false;
+                    self.start_change(
+                        Change::Policy {
+                            policy: self.policy,
+                            priority: self.rt_priority,
+                        },
+                        self.picked_processes(),
+                    );
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.ionice_modal {
+            let priority = &mut self.ionice_priority;
+            match key.code {
+                KeyCode::Esc => self.ionice_modal = false,
+                KeyCode::Tab => priority.class = priority.class.next(),
+                KeyCode::Char('k') | KeyCode::Up => {
+                    priority.level = priority.level.saturating_sub(1)
+                }
+                KeyCode::Char('j') | KeyCode::Down => priority.level = (priority.level + 1).min(7),
+                KeyCode::Char('s') => self.ionice_subtree = !self.ionice_subtree,
+                KeyCode::Enter => {
+                    self.ionice_modal = false;
+                    self.start_change(
+                        Change::IoPriority {
+                            priority: self.ionice_priority,
+                            subtree: self.ionice_subtree,
+                        },
+                        self.picked_processes(),
+                    );
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.renice_modal {
+            match key.code {
+                KeyCode::Esc => self.renice_modal = false,
+                KeyCode::Enter => match self.renice_input.trim().parse::<i32>() {
+                    Ok(nice) if (-20..=19).contains(&nice) => {
+                        self.renice_modal = false;
+                        self.start_change(Change::NiceTo(nice), self.picked_processes());
+                    }
+                    _ => self.set_status("Nice must be between -20 and 19".to_string(), true),
+                },
+                KeyCode::Char(c) if c.is_ascii_digit() || c == '-' => self.renice_input.push(c),
+                KeyCode::Backspace => {
+                    self.renice_input.pop();
+                }
+                _ => {}
+            }
+            return;
+        }
+        if let Some((change, pid, start_time)) = self.change_confirm.clone() {
+            match key.code {
+                KeyCode::Esc => {
+                    self.change_confirm = None;
+                    self.protect_input.clear();
+                }
+                KeyCode::Enter => {
+                    let typed = self
+                        .system
+                        .process(pid)
+                        .is_some_and(|p| p.name().to_string_lossy() == self.protect_input);
+                    self.change_confirm = None;
+                    self.protect_input.clear();
+                    if typed {
+                        self.apply_change(change, vec![(pid, start_time)], Some(pid));
+                    } else {
+                        self.set_status("Name does not match, nothing changed".to_string(), true);
+                    }
+                }
+                KeyCode::Char(c) => self.protect_input.push(c),
+                KeyCode::Backspace => {
+                    self.protect_input.pop();
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.history_modal {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => self.history_modal = false,
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.history_selected =
+                        (self.history_selected + 1).min(self.history.len().saturating_sub(1));
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.history_selected = self.history_selected.saturating_sub(1);
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.user_picker {
+            match key.code {
+                KeyCode::Esc => self.user_picker = false,
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.user_selected =
+                        (self.user_selected + 1).min(self.user_picker_entries.len());
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.user_selected = self.user_selected.saturating_sub(1);
+                }
+                KeyCode::Enter => {
+                    self.user_filter = match self.user_selected {
+                        0 => None,
+                        i => self.user_picker_entries.get(i - 1).map(|(uid, _)| *uid),
+                    };
+                    self.user_picker = false;
+                    self.table_state.select(Some(0));
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.preset_save_modal {
+            match key.code {
+                KeyCode::Esc => {
+                    self.preset_save_modal = false;
+                    self.preset_name_input.clear();
+                }
+                KeyCode::Enter => {
+                    self.save_preset();
+                    self.preset_save_modal = false;
+                    self.preset_name_input.clear();
+                }
+                KeyCode::Char(c) => self.preset_name_input.push(c),
+                KeyCode::Backspace => {
+                    self.preset_name_input.pop();
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.kill_modal {
+            if self.escalation.is_some() {
+                if key.code == KeyCode::Esc {
+                    self.cancel_escalation();
+                }
+                return;
+            }
+            if let Some(action) = self.protect_confirm {
+                match key.code {
+                    KeyCode::Esc => {
+                        self.protect_confirm = None;
+                        self.protect_input.clear();
+                    }
+                    KeyCode::Enter => {
+                        let typed = self
+                            .protected_target()
+                            .is_some_and(|(_, name)| name == self.protect_input);
+                        self.protect_confirm = None;
+                        self.protect_input.clear();
+                        if typed {
+                            self.run_kill_action(action, true);
+                        } else {
+                            self.set_status("Name does not match, nothing sent".to_string(), true);
+                        }
+                    }
+                    KeyCode::Char(c) => self.protect_input.push(c),
+                    KeyCode::Backspace => {
+                        self.protect_input.pop();
+                    }
+                    _ => {}
+                }
+                return;
+            }
+            if !self.kill_results.is_empty() {
+                if matches!(key.code, KeyCode::Esc | KeyCode::Enter) {
+                    self.close_kill_modal();
+                }
+                return;
+            }
+            match key.code {
+                KeyCode::Esc => self.close_kill_modal(),
+                KeyCode::Tab => {
+                    self.kill_scope = self.kill_scope.next();
+                    self.pin_kill_targets();
+                }
+                KeyCode::Up => self.signal_list_state.select_previous(),
+                KeyCode::Down => self.signal_list_state.select_next(),
+                KeyCode::PageUp => self.signal_list_state.scroll_up_by(10),
+                KeyCode::PageDown => self.signal_list_state.scroll_down_by(10),
+                KeyCode::Enter => {
+                    if let Some(number) = self.chosen_signal() {
+                        self.run_kill_action(Confirm::Signal(number), false);
+                    }
+                }
+                KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.run_kill_action(Confirm::Escalate, false);
+                }
+                KeyCode::Char(c) if c.is_ascii_alphanumeric() => {
+                    self.signal_input.push(c);
+                    self.select_typed_signal();
+                }
+                KeyCode::Backspace => {
+                    self.signal_input.pop();
+                    self.select_typed_signal();
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.find {
+            match key.code {
+                KeyCode::Esc => {
+                    self.find = false;
+                    self.find_textarea.select_all();
+                    self.find_textarea.cut();
+                }
+                KeyCode::Enter => {
+                    self.find = false;
+                }
+                _ => {
+                    if self.find_textarea.input(key) {
+                        self.jump_to_match(true, true);
+                    }
+                }
+            }
+            return;
+        }
+        if self.search {
+            if key.code == KeyCode::Esc {
+                self.search = false;
+            } else {
+                match key.code {
+                    KeyCode::Enter => {
+                        self.search = false;
+                    }
+                    KeyCode::Tab => {
+                        self.search_full = !self.search_full;
+                        self.update_search_block();
+                    }
+                    _ => {
+                        self.textarea.input(key);
+                    }
+                }
+            }
+            return;
+        }
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc | KeyCode::Char('q'))
+            | (KeyModifiers::CONTROL, KeyCode::Char('c') | KeyCode::Char('C')) => self.quit(),
+
+            (_, KeyCode::Char('j')) => {
+                self.table_state.select_next();
+            }
+            (_, KeyCode::Char('k')) => {
+                self.table_state.select_previous();
+            }
+            (_, KeyCode::Char('s')) => {
+                self.search = !self.search;
+            }
+            (_, KeyCode::Char('/')) => {
+                self.find = true;
+            }
+            (_, KeyCode::Char('n')) => {
+                self.jump_to_match(true, false);
+            }
+            (_, KeyCode::Char('N')) => {
+                self.jump_to_match(false, false);
+            }
+            (_, KeyCode::Char('d')) => {
+                self.prepare_kill_modal();
+            }
+            (_, KeyCode::Char('P')) => {
+                self.pattern_modal = true;
+                self.pattern_input.clear();
+            }
+            (_, KeyCode::Char('M')) => self.cycle_memory_metric(),
+            (_, KeyCode::Char('L')) => {
+                self.port_modal = true;
+                self.port_input.clear();
+                self.look_up_port();
+            }
+            (_, KeyCode::Char('p')) => {
+                self.kill_by_pid_modal = true;
+                self.kill_by_pid_input.clear();
+            }
+            (_, KeyCode::Enter) => self.open_details(),
+            (_, KeyCode::Char('o')) => {
+                self.sort_key = self.sort_key.next();
+            }
+            (_, KeyCode::Char('r')) => {
+                self.sort_reverse = !self.sort_reverse;
+            }
+            (_, KeyCode::Char('c')) => {
+                self.column_picker = true;
+            }
+            (_, KeyCode::Char('F')) => {
+                self.preset_picker = true;
+                self.preset_selected = 0;
+            }
+            (_, KeyCode::Char('S')) => {
+                self.preset_save_modal = true;
+                self.preset_name_input.clear();
+            }
+            (_, KeyCode::Char(' ')) => {
+                self.toggle_tag_selected();
+            }
+            (_, KeyCode::Char('a')) => {
+                self.tag_visible();
+            }
+            (_, KeyCode::Char('T')) => {
+                self.tag_subtree();
+            }
+            (_, KeyCode::Char('A')) => {
+                self.tagged.clear();
+            }
+            (_, KeyCode::Char('u')) => {
+                self.user_filter = match self.user_filter {
+                    Some(_) => None,
+                    None => Some(get_current_uid()),
+                };
+                self.table_state.select(Some(0));
+            }
+            (_, KeyCode::Char('U')) => {
+                self.open_user_picker();
+            }
+            (_, KeyCode::Char('K')) => {
+                self.hide_kernel_threads = !self.hide_kernel_threads;
+                self.table_state.select(Some(0));
+            }
+            (_, KeyCode::Char('z')) => self.toggle_stopped(),
+            (_, KeyCode::Char('Z')) => {
+                self.only_stopped = !self.only_stopped;
+                self.table_state.select(Some(0));
+            }
+            (_, KeyCode::Char('H')) => self.open_history(),
+            (_, KeyCode::Char('?')) => {
+                self.help_scroll = 0;
+                self.help_modal = true;
+            }
+            (_, KeyCode::Char('+')) => {
+                self.start_change(Change::NiceBy(1), self.picked_processes())
+            }
+            (_, KeyCode::Char('-')) => {
+                self.start_change(Change::NiceBy(-1), self.picked_processes())
+            }
+            (_, KeyCode::Char('C')) => self.open_affinity_modal(),
+            (_, KeyCode::Char('Y')) => {
+                if let Some((pid, _)) = self.picked_processes().first() {
+                    let info = self.info.get(pid);
+                    self.policy = info.and_then(|i| i.policy).unwrap_or_default();
+                    self.rt_priority = info.and_then(|i| i.rt_priority).unwrap_or(0) as i32;
+                    self.policy_modal = true;
+                }
+            }
+            (_, KeyCode::Char('I')) => {
+                if let Some((pid, _)) = self.picked_processes().first() {
+                    let current = self.info.get(pid).and_then(|info| info.io_priority);
+                    self.ionice_priority = current.unwrap_or_default();
+                    self.ionice_subtree = false;
+                    self.ionice_modal = true;
+                }
+            }
+            (_, KeyCode::Char('R')) => {
+                if let Some((pid, _)) = self.picked_processes().first() {
+                    let nice = self.info.get(pid).and_then(|info| info.nice).unwrap_or(0);
+                    self.renice_input = nice.to_string();
+                    self.renice_modal = true;
+                }
+            }
+            (_, KeyCode::Char(c @ '1'..='9')) => {
+                self.apply_preset(c as usize - '1' as usize);
+            }
+            _ => {}
+        }
+    }
+
+    fn on_mouse_event(&mut self, mouse: MouseEvent) {
+        if self.search
+            || self.find
+            || self.kill_modal
+            || self.kill_by_pid_modal
+            || self.pattern_modal
+            || self.column_picker
+            || self.preset_picker
+            || self.preset_save_modal
+            || self.user_picker
+            || self.history_modal
+            || self.renice_modal
+            || self.affinity_target.is_some()
+            || self.ionice_modal
+            || self.policy_modal
+            || self.port_modal
+            || self.help_modal
+            || self.change_confirm.is_some()
+        {
+            return;
+        }
+
+        if self.details_target.is_some() {
+            match mouse.kind {
+                MouseEventKind::ScrollUp => {
+                    self.details_scroll = self.details_scroll.saturating_sub(3);
+                }
+                MouseEventKind::ScrollDown => {
+                    self.details_scroll = self.details_scroll.saturating_add(3);
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match mouse.kind {
+            MouseEventKind::ScrollUp => {
+                if let Some(selected) = self.table_state.selected() {
+                    if selected > 0 {
+                        self.table_state.select(Some(selected - 1));
+                    }
+                }
+            }
+            MouseEventKind::ScrollDown => {
+                if let Some(selected) = self.table_state.selected() {
+                    if selected + 1 < self.visible_processes().len() {
+                        self.table_state.select(Some(selected + 1));
+                    }
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                let visible = self.visible_processes().len();
+
+                // Check if click is within the process list area
+                if mouse.column >= self.process_list_area.x && mouse.column < self.process_list_area.x + self.process_list_area.width
+                    && mouse.row >= self.process_list_area.y + 2  // Skip header and border
+                    && mouse.row < self.process_list_area.y + self.process_list_area.height
+                {
+                    // Calculate which row was clicked
+                    let clicked_row = (mouse.row - (self.process_list_area.y + 2)) as usize;
+                    if clicked_row < visible {
+                        self.table_state.select(Some(clicked_row));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The tagged processes, or the selected one if nothing is tagged.
+    fn picked_processes(&self) -> Vec<(sysinfo::Pid, u64)> {
+        if self.tagged.is_empty() {
+            self.selected_process()
+                .map(|process| vec![(process.pid(), process.start_time())])
+                .unwrap_or_default()
+        } else {
+            self.tagged
+                .iter()
+                .map(|(pid, start)| (*pid, *start))
+                .collect()
+        }
+    }
+
+    /// Resumes the picked processes with SIGCONT if all of them are stopped, and stops
+    /// them with SIGSTOP otherwise. Goes through the kill modal, so protected processes
+    /// are skipped or need confirmation just like for any other signal.
+    fn toggle_stopped(&mut self) {
+        let picked = self.picked_processes();
+        let all_stopped = picked.iter().all(|(pid, _)| {
+            self.system
+                .process(*pid)
+                .is_some_and(|process| process.status() == sysinfo::ProcessStatus::Stop)
+        });
+        let signal = if all_stopped {
+            libc::SIGCONT
+        } else {
+            libc::SIGSTOP
+        };
+        let pids: Vec<_> = picked.iter().map(|(pid, _)| *pid).collect();
+        // Tags stay, so the same processes can be resumed with the next press
+        self.open_kill_modal(picked);
+        if self.kill_modal {
+            self.run_kill_action(Confirm::Signal(signal), false);
+        }
+        // A key press should not leave a dialog behind, so bulk results go to the status line
+        if !self.kill_results.is_empty() {
+            let outcomes: Vec<_> = self
+                .kill_results
+                .iter()
+                .map(|r| match &r.result {
+                    Ok(()) => format!("{} ({}) ok", r.pid, r.name),
+                    Err(err) => format!("{} ({}) {}", r.pid, r.name, err),
+                })
+                .collect();
+            let failed = self.kill_results.iter().any(|r| r.result.is_err());
+            let text = format!("{}: {}", signals::name(signal), outcomes.join(", "));
+            self.set_status(text, failed);
+            self.close_kill_modal();
+        }
+        // Show the new state right away, so pressing the key again does the right thing
+        self.refresh_pids(&pids);
+    }
+
+    /// Refreshes `pids` outside the regular refresh, including the command line and user
+    /// that protection rules and the kill preview need for processes not seen before.
+    fn refresh_pids(&mut self, pids: &[sysinfo::Pid]) {
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(pids),
+            true,
+            ProcessRefreshKind::nothing()
+                .with_memory()
+                .with_cpu()
+                .with_exe(UpdateKind::OnlyIfNotSet)
+                .with_cmd(UpdateKind::OnlyIfNotSet)
+                .with_user(UpdateKind::OnlyIfNotSet),
+        );
+    }
+
+    fn prepare_kill_modal(&mut self) {
+        self.open_kill_modal(self.picked_processes());
+        self.kill_picked_tags = !self.tagged.is_empty();
+    }
+
+    fn open_affinity_modal(&mut self) {
+        let Some(process) = self.selected_process() else {
+            return;
+        };
+        let (pid, start_time) = (process.pid(), process.start_time());
+        match sched::affinity(pid) {
+            Ok(cpus) => {
+                let count = sched::cpu_count().max(cpus.last().map_or(0, |cpu| cpu + 1));
+                self.affinity_cpus = (0..count).map(|cpu| cpus.contains(&cpu)).collect();
+                self.affinity_selected = 0;
+                self.affinity_target = Some((pid, start_time));
+            }
+            Err(err) => {
+                let text = format!(
+                    "Cannot read affinity of PID {}: {}",
+                    pid,
+                    sched::describe_error(&err)
+                );
+                self.set_status(text, true);
+            }
+        }
+    }
+
+    fn apply_affinity(&mut self) {
+        let Some((pid, start_time)) = self.affinity_target else {
+            return;
+        };
+        let cpus: Vec<_> = (0..self.affinity_cpus.len())
+            .filter(|cpu| self.affinity_cpus[*cpu])
+            .collect();
+        if cpus.is_empty() {
+            self.set_status("Choose at least one CPU".to_string(), true);
             return;
         }
+        self.affinity_target = None;
+        let change = Change::Affinity {
+            cpus,
+            all_threads: self.affinity_all_threads,
+        };
+        self.start_change(change, vec![(pid, start_time)]);
+    }
 
-        match mouse.kind {
-            MouseEventKind::ScrollUp => {
-                if let Some(selected) = self.table_state.selected() {
-                    if selected > 0 {
-                        self.table_state.select(Some(selected - 1));
-                    }
-                }
+    /// Only real-time policies have a priority, from 1 to 99.
+    fn change_rt_priority(&mut self, delta: i32) {
+        if self.policy.is_realtime() {
+            self.rt_priority = (self.rt_priority + delta).clamp(1, 99);
+        }
+    }
+
+    /// The picked processes, with all their descendants if the dialog asks for them.
+    fn ionice_targets(&self) -> Vec<(sysinfo::Pid, u64)> {
+        let picked = self.picked_processes();
+        if self.ionice_subtree {
+            self.with_descendants(&picked)
+        } else {
+            picked
+        }
+    }
+
+    /// `roots` with all their descendants, threads left out.
+    fn with_descendants(&self, roots: &[(sysinfo::Pid, u64)]) -> Vec<(sysinfo::Pid, u64)> {
+        let processes = self.system.processes();
+        let roots: Vec<_> = roots.iter().map(|(pid, _)| *pid).collect();
+        subtrees(processes, &roots)
+            .into_iter()
+            .filter_map(|pid| processes.get(&pid))
+            .filter(|process| process.thread_kind() != Some(sysinfo::ThreadKind::Userland))
+            .map(|process| (process.pid(), process.start_time()))
+            .collect()
+    }
+
+    /// Makes `change` to `picked`. Like signals, bulk changes skip protected processes
+    /// and a single protected one needs its name typed first.
+    fn start_change(&mut self, change: Change, picked: Vec<(sysinfo::Pid, u64)>) {
+        if let [(pid, start_time)] = picked[..] {
+            if self
+                .system
+                .process(pid)
+                .is_some_and(|process| self.protection.protects(process))
+            {
+                self.change_confirm = Some((change, pid, start_time));
+                self.protect_input.clear();
+                return;
             }
-            MouseEventKind::ScrollDown => {
-                if let Some(selected) = self.table_state.selected() {
-                    let processes: Vec<_> = self.system.processes().iter().collect();
-                    if selected < processes.len() - 1 {
-                        self.table_state.select(Some(selected + 1));
-                    }
-                }
+        }
+        self.apply_change(change, picked, None);
+    }
+
+    /// Makes `change` to `picked`, leaving out protected processes other than `confirmed`.
+    fn apply_change(
+        &mut self,
+        change: Change,
+        picked: Vec<(sysinfo::Pid, u64)>,
+        confirmed: Option<sysinfo::Pid>,
+    ) {
+        let mut skipped = vec![];
+        let targets = self.skip_protected(picked, confirmed, &mut skipped);
+        match change {
+            Change::NiceBy(delta) => self.renice(targets, skipped, |nice| nice + delta),
+            Change::NiceTo(nice) => self.renice(targets, skipped, |_| nice),
+            Change::IoPriority { priority, subtree } => {
+                // Protected processes are left out before their descendants are added,
+                // and protected descendants are left out as well
+                let targets = if subtree {
+                    let descendants = self.with_descendants(&targets);
+                    self.skip_protected(descendants, confirmed, &mut skipped)
+                } else {
+                    targets
+                };
+                self.change_processes(
+                    targets,
+                    skipped,
+                    "I/O priority",
+                    "ionice",
+                    |pid, start_time| {
+                        let result = sched::set_io_priority(pid, Some(start_time), priority)
+                            .map_err(|err| sched::describe_error(&err));
+                        (priority.to_string(), result)
+                    },
+                );
             }
-            MouseEventKind::Down(MouseButton::Left) => {
-                // Get the filtered processes first
-                let mut rows: Vec<(sysinfo::Pid, &sysinfo::Process)> = vec![];
-                for (pid, process) in self.system.processes() {
-                    rows.push((*pid, process));
-                }
-                rows.sort_by(|a, b| {
-                    let a_cpu = a.1.cpu_usage();
-                    let b_cpu = b.1.cpu_usage();
-                    b_cpu.partial_cmp(&a_cpu).unwrap()
+            Change::Policy { policy, priority } => {
+                let setting = policy_setting(policy, priority);
+                self.change_processes(targets, skipped, "policy", "sched", |pid, start_time| {
+                    let result = sched::set_scheduler(pid, Some(start_time), policy, priority)
+                        .map_err(|err| match err.raw_os_error() {
+                            Some(libc::EPERM) if policy.is_realtime() => {
+                                "EPERM: real-time policies need root or CAP_SYS_NICE".to_string()
+                            }
+                            _ => sched::describe_error(&err),
+                        });
+                    (setting.clone(), result)
                 });
-                let text = self.textarea.lines().first().unwrap();
-                let filtered: Vec<_> = rows
-                    .into_iter()
-                    .filter(|(_pid, process)| {
-                        let name = process.name().to_string_lossy().to_string();
-                        let cpu = process.cpu_usage().to_string();
-                        let pid = process.pid().to_string();
-                        [pid, name, cpu]
-                            .iter()
-                            .any(|cell| cell.to_lowercase().contains(&text.to_lowercase()))
-                    })
-                    .collect();
-
-                // Check if click is within the process list area
-                if mouse.column >= self.process_list_area.x && mouse.column < self.process_list_area.x + self.process_list_area.width
-                    && mouse.row >= self.process_list_area.y + 2  // Skip header and border
-                    && mouse.row < self.process_list_area.y + self.process_list_area.height
-                {
-                    // Calculate which row was clicked
-                    let clicked_row = (mouse.row - (self.process_list_area.y + 2)) as usize;
-                    if clicked_row < filtered.len() {
-                        self.table_state.select(Some(clicked_row));
-                    }
-                }
             }
-            _ => {}
+            Change::Affinity { cpus, all_threads } => {
+                let setting = affinity_setting(&cpus, all_threads);
+                self.change_processes(
+                    targets,
+                    skipped,
+                    "CPU affinity",
+                    "affinity",
+                    |pid, start_time| {
+                        let result = sched::set_affinity(pid, Some(start_time), &cpus, all_threads)
+                            .map_err(|err| sched::describe_error(&err));
+                        (setting.clone(), result)
+                    },
+                );
+            }
         }
     }
 
-    fn prepare_kill_modal(&mut self) {
-        // Build the same filtered/visible process list as in render_processes
-        let mut rows: Vec<(sysinfo::Pid, &sysinfo::Process)> = vec![];
-        for (pid, process) in self.system.processes() {
-            rows.push((*pid, process));
-        }
-        rows.sort_by(|a, b| {
-            let a_cpu = a.1.cpu_usage();
-            let b_cpu = b.1.cpu_usage();
-            b_cpu.partial_cmp(&a_cpu).unwrap()
+    /// Sets the nice value of `targets` to `new_nice` of their current one,
+    /// clamped to the valid range.
+    fn renice(
+        &mut self,
+        targets: Vec<(sysinfo::Pid, u64)>,
+        skipped: Vec<SignalResult>,
+        new_nice: impl Fn(i32) -> i32,
+    ) {
+        self.change_processes(targets, skipped, "nice", "renice", |pid, start_time| {
+            let current = procfs::info(pid).nice.unwrap_or(0) as i32;
+            let nice = new_nice(current).clamp(-20, 19);
+            let result = sched::set_nice(pid, Some(start_time), nice)
+                .map_err(|err| sched::describe_error(&err));
+            (nice.to_string(), result)
         });
-        let text = self.textarea.lines().first().unwrap();
-        let filtered: Vec<_> = rows
+    }
+
+    /// `targets` without protected processes (except `confirmed`), which are added to
+    /// `skipped` instead.
+    fn skip_protected(
+        &self,
+        targets: Vec<(sysinfo::Pid, u64)>,
+        confirmed: Option<sysinfo::Pid>,
+        skipped: &mut Vec<SignalResult>,
+    ) -> Vec<(sysinfo::Pid, u64)> {
+        targets
             .into_iter()
-            .filter(|(_pid, process)| {
-                let name = process.name().to_string_lossy().to_string();
-                let cpu = process.cpu_usage().to_string();
-                let pid = process.pid().to_string();
-                [pid, name, cpu]
+            .filter(|(pid, _)| match self.system.process(*pid) {
+                Some(process) if Some(*pid) != confirmed && self.protection.protects(process) => {
+                    skipped.push(SignalResult {
+                        pid: *pid,
+                        name: process.name().to_string_lossy().to_string(),
+                        result: Err("skipped (protected)".to_string()),
+                    });
+                    false
+                }
+                _ => true,
+            })
+            .collect()
+    }
+
+    /// Applies a change to each of `targets` and reports the outcome in the status line
+    /// and the audit log, together with the `skipped` processes. `apply` returns the new
+    /// setting (e.g. `5` for a nice value) with the result; `what` names the setting in
+    /// messages, `action` in the audit log.
+    fn change_processes(
+        &mut self,
+        targets: Vec<(sysinfo::Pid, u64)>,
+        skipped: Vec<SignalResult>,
+        what: &str,
+        action: &str,
+        mut apply: impl FnMut(sysinfo::Pid, u64) -> (String, std::result::Result<(), String>),
+    ) {
+        let mut results = skipped;
+        let mut settings = BTreeSet::new();
+        let mut entries = vec![];
+        for (pid, start_time) in targets {
+            let (setting, result) = apply(pid, start_time);
+            let action = format!("{} {}", action, setting);
+            entries.push(self.audit_entry(pid, Some(start_time), action, &result));
+            settings.insert(setting);
+            results.push(SignalResult {
+                pid,
+                name: self
+                    .system
+                    .process(pid)
+                    .map(|p| p.name().to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                result,
+            });
+            self.info.insert(pid, procfs::info(pid));
+        }
+
+        // Relative changes (like nice +1) can end with a different setting per process
+        let to = match settings.iter().collect::<Vec<_>>().as_slice() {
+            [setting] => format!(" to {}", setting),
+            _ => String::new(),
+        };
+        let failed: Vec<_> = results.iter().filter(|r| r.result.is_err()).collect();
+        let text = match (results.as_slice(), failed.as_slice()) {
+            ([], _) => return,
+            ([single], []) => format!("Set {} of PID {} ({}){}", what, single.pid, single.name, to),
+            ([single], [_]) => format!(
+                "Failed to set {} of PID {} ({}): {}",
+                what,
+                single.pid,
+                single.name,
+                single.result.as_ref().unwrap_err()
+            ),
+            (_, []) => format!("Set {} of {} processes{}", what, results.len(), to),
+            (_, failed) => format!(
+                "Set {} of {} of {} processes, first error: PID {}: {}",
+                what,
+                results.len() - failed.len(),
+                results.len(),
+                failed[0].pid,
+                failed[0].result.as_ref().unwrap_err()
+            ),
+        };
+        self.set_status(text, !failed.is_empty());
+        self.audit(entries);
+    }
+
+    fn open_kill_modal(&mut self, picked: Vec<(sysinfo::Pid, u64)>) {
+        self.kill_picked = picked;
+        self.kill_picked_tags = false;
+        self.kill_scope = Scope::default();
+        self.pin_kill_targets();
+        self.kill_results.clear();
+        self.signal_input.clear();
+        let last = self
+            .signal_list
+            .iter()
+            .position(|signal| signal.number == self.last_signal);
+        self.signal_list_state.select(last);
+        self.kill_modal = !self.kill_targets.is_empty();
+    }
+
+    /// Pins the processes covered by the current kill scope, in the order they are signalled.
+    fn pin_kill_targets(&mut self) {
+        let mut skipped: Vec<_> = self
+            .kill_picked
+            .iter()
+            .filter(|(pid, _)| {
+                self.system
+                    .process(*pid)
+                    .and_then(|process| process.thread_kind())
+                    == Some(sysinfo::ThreadKind::Userland)
+            })
+            .map(|(pid, _)| format!("thread {} (signal its process instead)", pid))
+            .collect();
+        let mut picked = self.scoped_kill_targets();
+        if picked.len() > 1 {
+            // Bulk actions never touch protected processes, single ones ask for confirmation
+            picked.retain(|(pid, _)| match self.system.process(*pid) {
+                Some(process) if self.protection.protects(process) => {
+                    skipped.push(format!(
+                        "protected PID {} ({})",
+                        pid,
+                        process.name().to_string_lossy()
+                    ));
+                    false
+                }
+                _ => true,
+            });
+        }
+        let group = self.kill_scope == Scope::Group;
+        self.kill_targets = picked
+            .into_iter()
+            .filter_map(
+                |(pid, start_time)| match Target::pin(pid, Some(start_time)) {
+                    Ok(mut target) => {
+                        // Read while the identity is known to match, not again at send time
+                        if group {
+                            target.pgid = procfs::process_group(pid);
+                        }
+                        Some(target)
+                    }
+                    Err(err) => {
+                        skipped.push(format!("PID {}: {}", pid, signals::describe_error(&err)));
+                        None
+                    }
+                },
+            )
+            .collect();
+        if !skipped.is_empty() {
+            self.set_status(format!("Skipped {}", skipped.join(", ")), true);
+        }
+    }
+
+    /// The picked processes extended according to `kill_scope`. procman itself and
+    /// threads (which are signalled through their process) are left out.
+    fn scoped_kill_targets(&self) -> Vec<(sysinfo::Pid, u64)> {
+        let processes = self.system.processes();
+        let roots: Vec<_> = self.kill_picked.iter().map(|(pid, _)| *pid).collect();
+        let pids = match self.kill_scope {
+            Scope::Picked => {
+                return self
+                    .kill_picked
+                    .iter()
+                    .filter(|(pid, _)| {
+                        processes.get(pid).map(|process| process.thread_kind())
+                            != Some(Some(sysinfo::ThreadKind::Userland))
+                    })
+                    .copied()
+                    .collect()
+            }
+            Scope::TreeParentsFirst => subtrees(processes, &roots),
+            Scope::TreeLeavesFirst => {
+                let mut pids = subtrees(processes, &roots);
+                pids.reverse();
+                pids
+            }
+            Scope::Group | Scope::Session => {
+                let id = if self.kill_scope == Scope::Group {
+                    procfs::process_group
+                } else {
+                    procfs::session
+                };
+                let ids: BTreeSet<_> = roots.iter().filter_map(|pid| id(*pid)).collect();
+                let mut members: Vec<_> = processes
+                    .keys()
+                    .copied()
+                    .filter(|pid| id(*pid).is_some_and(|id| ids.contains(&id)))
+                    .collect();
+                members.sort();
+                members
+            }
+        };
+        let own_pid = sysinfo::Pid::from_u32(std::process::id());
+        pids.into_iter()
+            .filter(|pid| *pid != own_pid)
+            .filter_map(|pid| {
+                let process = processes.get(&pid)?;
+                if process.thread_kind() == Some(sysinfo::ThreadKind::Userland) {
+                    return None;
+                }
+                // Keep the start time the picked processes had when they were picked
+                let start_time = self
+                    .kill_picked
                     .iter()
-                    .any(|cell| cell.to_lowercase().contains(&text.to_lowercase()))
+                    .find(|(picked, _)| *picked == pid)
+                    .map_or(process.start_time(), |(_, start)| *start);
+                Some((pid, start_time))
             })
+            .collect()
+    }
+
+    /// Position of the first listed signal whose number or name matches what has been typed.
+    fn typed_signal(&self) -> Option<usize> {
+        let input = self.signal_input.to_uppercase();
+        let name = input.strip_prefix("SIG").unwrap_or(&input);
+        self.signal_list.iter().position(|signal| {
+            signal.number.to_string() == input
+                || (!name.is_empty() && signal.name.starts_with(name))
+        })
+    }
+
+    fn select_typed_signal(&mut self) {
+        if let Some(found) = self.typed_signal() {
+            self.signal_list_state.select(Some(found));
+        }
+    }
+
+    /// The typed signal if it is a valid name or number (including unlisted real-time
+    /// signals), the highlighted one otherwise.
+    fn chosen_signal(&self) -> Option<i32> {
+        let highlighted = || {
+            self.signal_list_state
+                .selected()
+                .and_then(|i| self.signal_list.get(i))
+                .map(|signal| signal.number)
+        };
+        if self.signal_input.is_empty() {
+            highlighted()
+        } else {
+            signals::parse(&self.signal_input).or_else(|| self.typed_signal().and(highlighted()))
+        }
+    }
+
+    /// Runs `action` unless the only target is protected and the user has not
+    /// `confirmed` it by typing the process name yet.
+    fn run_kill_action(&mut self, action: Confirm, confirmed: bool) {
+        if !confirmed && self.protected_target().is_some() {
+            self.protect_confirm = Some(action);
+            self.protect_input.clear();
+            return;
+        }
+        match action {
+            Confirm::Signal(number) => self.send_signal(number),
+            Confirm::Escalate => self.start_escalation(),
+        }
+    }
+
+    /// PID and name of the single kill target if it is protected. Bulk targets have
+    /// their protected processes removed when they are pinned.
+    fn protected_target(&self) -> Option<(sysinfo::Pid, String)> {
+        let [target] = self.kill_targets.as_slice() else {
+            return None;
+        };
+        let process = self.system.process(target.pid)?;
+        self.protection
+            .protects(process)
+            .then(|| (target.pid, process.name().to_string_lossy().to_string()))
+    }
+
+    fn send_signal(&mut self, number: i32) {
+        let results = self.deliver(number);
+        if self.kill_targets.len() > 1 {
+            // Keep the modal open to show what happened to each target
+            self.kill_results = results;
+        } else {
+            self.close_kill_modal();
+        }
+    }
+
+    /// Sends `number` to the kill targets the way the current scope asks for.
+    fn deliver(&mut self, number: i32) -> Vec<SignalResult> {
+        self.last_signal = number;
+        let targets = std::mem::take(&mut self.kill_targets);
+        let results = if self.kill_scope == Scope::Group {
+            self.signal_groups(&targets, number)
+        } else {
+            self.signal_targets(&targets, number)
+        };
+        self.kill_targets = targets;
+        if self.kill_picked_tags {
+            self.tagged.clear();
+        }
+        results
+    }
+
+    /// Sends SIGTERM and keeps the modal open with a countdown, after which
+    /// `run_escalation` sends SIGKILL to whatever is still running.
+    fn start_escalation(&mut self) {
+        self.escalation =
+            Some(Instant::now() + Duration::from_secs(self.config.escalation_timeout));
+        let results = self.deliver(libc::SIGTERM);
+        if results.iter().all(|r| r.result.is_err()) {
+            // Nothing to wait for, show why instead
+            self.escalation = None;
+            self.kill_results = results;
+        }
+    }
+
+    fn run_escalation(&mut self) {
+        let Some(due) = self.escalation else {
+            return;
+        };
+        let pids: Vec<_> = self.kill_targets.iter().map(|target| target.pid).collect();
+        self.system
+            .refresh_processes(ProcessesToUpdate::Some(&pids), true);
+        let alive: Vec<_> = self
+            .kill_targets
+            .iter()
+            .filter(|target| self.is_alive(target.pid, target.start_time))
+            .map(|target| target.pid)
             .collect();
-        if let Some(selected) = self.table_state.selected() {
-            if selected < filtered.len() {
-                let (pid, _process) = filtered[selected];
-                self.kill_modal = true;
-                self.kill_pid = Some(pid);
+        if alive.is_empty() {
+            self.escalation = None;
+            let text = format!(
+                "All {} processes exited after SIGTERM, SIGKILL not needed",
+                self.kill_targets.len()
+            );
+            self.set_status(text, false);
+            self.close_kill_modal();
+        } else if Instant::now() >= due {
+            self.escalation = None;
+            if self.kill_scope != Scope::Group {
+                // Processes that are gone would only show up as ESRCH failures
+                self.kill_targets
+                    .retain(|target| alive.contains(&target.pid));
             }
+            self.kill_results = self.deliver(libc::SIGKILL);
         }
     }
 
-    fn send_signal(&mut self, sig: Signal) {
-        if let Some(pid) = self.kill_pid {
-            for (proc_pid, process) in self.system.processes() {
-                if *proc_pid == pid {
-                    let _ = process.kill_with(sig);
+    fn cancel_escalation(&mut self) {
+        self.escalation = None;
+        self.set_status("Escalation cancelled, SIGKILL not sent".to_string(), false);
+        self.close_kill_modal();
+    }
+
+    /// Whether `pid` still runs the process that started at `start_time`. Zombies count as exited.
+    fn is_alive(&self, pid: sysinfo::Pid, start_time: Option<u64>) -> bool {
+        self.system.process(pid).is_some_and(|p| {
+            p.status() != sysinfo::ProcessStatus::Zombie
+                && start_time.is_none_or(|start| p.start_time() == start)
+        })
+    }
+
+    /// Sends `number` to every target, reports the outcome in the status line and
+    /// schedules a check that the processes are actually gone.
+    fn signal_targets(&mut self, targets: &[Target], number: i32) -> Vec<SignalResult> {
+        let results: Vec<SignalResult> = targets
+            .iter()
+            .map(|target| SignalResult {
+                pid: target.pid,
+                name: self
+                    .system
+                    .process(target.pid)
+                    .map(|p| p.name().to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                result: target
+                    .signal(number)
+                    .map_err(|err| signals::describe_error(&err)),
+            })
+            .collect();
+        let delivered: Vec<_> = results
+            .iter()
+            .zip(targets)
+            .filter(|(r, _)| r.result.is_ok())
+            .map(|(r, target)| (r.pid, r.name.clone(), target.start_time))
+            .collect();
+        self.report_signal(&results, delivered, number);
+        let entries = results
+            .iter()
+            .zip(targets)
+            .map(|(r, target)| {
+                self.audit_entry(
+                    target.pid,
+                    target.start_time,
+                    signals::name(number),
+                    &r.result,
+                )
+            })
+            .collect();
+        self.audit(entries);
+        results
+    }
+
+    /// Sends `number` to the process groups of the pinned picked processes with `killpg`,
+    /// which also reaches group members that appeared after the preview was shown.
+    /// There is one result per group, named after its leader.
+    fn signal_groups(&mut self, targets: &[Target], number: i32) -> Vec<SignalResult> {
+        // killpg cannot leave members out, so a group with a protected member is refused
+        let protected: BTreeMap<_, _> = self
+            .system
+            .processes()
+            .values()
+            .filter(|process| self.protection.protects(process))
+            .filter_map(|process| {
+                let name = process.name().to_string_lossy().to_string();
+                Some((procfs::process_group(process.pid())?, name))
+            })
+            .collect();
+        // Each group with the picked process its ID was read from when it was pinned
+        let mut groups: BTreeMap<sysinfo::Pid, &Target> = BTreeMap::new();
+        let mut ungrouped = vec![];
+        for (pid, _) in &self.kill_picked {
+            // Picked processes that could not be pinned were reported as skipped already
+            let Some(target) = targets.iter().find(|target| target.pid == *pid) else {
+                continue;
+            };
+            match target.pgid {
+                Some(pgid) => {
+                    groups.entry(pgid).or_insert(target);
                 }
+                // Kernel threads belong to no group, killpg(0) would hit procman's own
+                None => ungrouped.push(SignalResult {
+                    pid: *pid,
+                    name: self
+                        .system
+                        .process(*pid)
+                        .map(|p| p.name().to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    result: Err("not in a process group, skipped".to_string()),
+                }),
+            }
+        }
+        let results: Vec<SignalResult> = groups
+            .iter()
+            .map(|(pgid, picked)| SignalResult {
+                pid: *pgid,
+                name: format!(
+                    "group of {}",
+                    self.system
+                        .process(*pgid)
+                        .map(|p| p.name().to_string_lossy().to_string())
+                        .unwrap_or_else(|| "exited leader".to_string())
+                ),
+                result: if let Some(name) = protected.get(pgid) {
+                    Err(format!("group contains protected {}, refusing", name))
+                } else {
+                    // The group ID is only trusted while its picked process is still the same
+                    signals::verify_identity(picked.pid, picked.start_time)
+                        .and_then(|()| signals::send_group(*pgid, number))
+                        .map_err(|err| signals::describe_error(&err))
+                },
+            })
+            .chain(ungrouped)
+            .collect();
+        let delivered_groups: Vec<_> = results
+            .iter()
+            .filter(|r| r.result.is_ok())
+            .map(|r| r.pid)
+            .collect();
+        let delivered: Vec<_> = targets
+            .iter()
+            .filter(|target| {
+                target
+                    .pgid
+                    .is_some_and(|pgid| delivered_groups.contains(&pgid))
+            })
+            .map(|target| {
+                let name = self
+                    .system
+                    .process(target.pid)
+                    .map(|p| p.name().to_string_lossy().to_string())
+                    .unwrap_or_default();
+                (target.pid, name, target.start_time)
+            })
+            .collect();
+        self.report_signal(&results, delivered, number);
+        let entries = results
+            .iter()
+            .map(|r| {
+                let start_time = procfs::start_time(r.pid);
+                let action = format!("killpg {}", signals::name(number));
+                self.audit_entry(r.pid, start_time, action, &r.result)
+            })
+            .collect();
+        self.audit(entries);
+        results
+    }
+
+    fn audit_entry(
+        &self,
+        pid: sysinfo::Pid,
+        start_time: Option<u64>,
+        action: String,
+        result: &std::result::Result<(), String>,
+    ) -> audit::Entry {
+        let mut entry = audit::Entry::now(pid.as_u32(), action, result);
+        entry.start_time = start_time;
+        if let Some(process) = self.system.process(pid) {
+            entry.name = process.name().to_string_lossy().to_string();
+            entry.cmdline = process_cmd(process);
+        }
+        entry
+    }
+
+    /// Appends `entries` to the audit log, a failure is shown in the status line.
+    fn audit(&mut self, entries: Vec<audit::Entry>) {
+        let Some(path) = &self.audit_path else {
+            return;
+        };
+        if let Err(err) = audit::append(path, &entries) {
+            let text = format!("Cannot write audit log {}: {}", path.display(), err);
+            self.set_status(text, true);
+        }
+    }
+
+    fn open_history(&mut self) {
+        let Some(path) = &self.audit_path else {
+            self.set_status(
+                "No audit log location, set audit_log in the config".to_string(),
+                true,
+            );
+            return;
+        };
+        match audit::read(path) {
+            Ok(mut entries) => {
+                entries.reverse();
+                self.history = entries;
+                self.history_selected = 0;
+                self.history_modal = true;
             }
+            Err(err) => {
+                let text = format!("Cannot read audit log {}: {}", path.display(), err);
+                self.set_status(text, true);
+            }
+        }
+    }
+
+    /// Reports the outcome of sending `number` in the status line and schedules a check
+    /// that the `delivered` processes are actually gone.
+    fn report_signal(
+        &mut self,
+        results: &[SignalResult],
+        delivered: Vec<(sysinfo::Pid, String, Option<u64>)>,
+        number: i32,
+    ) {
+        let failed: Vec<_> = results.iter().filter(|r| r.result.is_err()).collect();
+        let signal = signals::name(number);
+        let text = match (results, failed.as_slice()) {
+            ([single], []) => format!("Sent {} to PID {} ({})", signal, single.pid, single.name),
+            ([single], [_]) => format!(
+                "Failed to send {} to PID {}: {}",
+                signal,
+                single.pid,
+                single.result.as_ref().unwrap_err()
+            ),
+            (_, []) => format!("Sent {} to {} processes", signal, results.len()),
+            (_, failed) => format!(
+                "Sent {} to {} of {} processes, first error: PID {}: {}",
+                signal,
+                results.len() - failed.len(),
+                results.len(),
+                failed[0].pid,
+                failed[0].result.as_ref().unwrap_err()
+            ),
+        };
+        self.set_status(text, !failed.is_empty());
+
+        // A running escalation does its own checking
+        if signals::terminates(number) && !delivered.is_empty() && self.escalation.is_none() {
+            self.exit_checks.push(ExitCheck {
+                targets: delivered,
+                signal: number,
+                due: Instant::now() + EXIT_CHECK_DELAY,
+            });
+        }
+    }
+
+    /// Reports whether the processes of due exit checks are gone. Zombies count as exited.
+    fn run_exit_checks(&mut self) {
+        let now = Instant::now();
+        let (due, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.exit_checks)
+            .into_iter()
+            .partition(|check| check.due <= now);
+        self.exit_checks = pending;
+        for check in due {
+            let pids: Vec<_> = check.targets.iter().map(|(pid, _, _)| *pid).collect();
+            self.system
+                .refresh_processes(ProcessesToUpdate::Some(&pids), true);
+            let alive: Vec<_> = check
+                .targets
+                .iter()
+                .filter(|(pid, _, start_time)| self.is_alive(*pid, *start_time))
+                .collect();
+            let signal = signals::name(check.signal);
+            let text = match (check.targets.as_slice(), alive.as_slice()) {
+                ([(pid, name, _)], []) => format!("PID {} ({}) exited after {}", pid, name, signal),
+                ([(pid, name, _)], _) => format!(
+                    "PID {} ({}) is still running {}s after {}",
+                    pid,
+                    name,
+                    EXIT_CHECK_DELAY.as_secs(),
+                    signal
+                ),
+                (targets, alive) => format!(
+                    "{} of {} processes exited after {}",
+                    targets.len() - alive.len(),
+                    targets.len(),
+                    signal
+                ),
+            };
+            self.set_status(text, !alive.is_empty());
         }
+    }
+
+    fn set_status(&mut self, text: String, error: bool) {
+        self.status = Some(Status {
+            text,
+            error,
+            shown_at: Instant::now(),
+        });
+    }
+
+    fn close_kill_modal(&mut self) {
         self.kill_modal = false;
-        self.kill_pid = None;
+        self.kill_targets.clear();
+        self.kill_picked.clear();
+        self.protect_confirm = None;
+        self.protect_input.clear();
+        self.kill_results.clear();
+    }
+
+    fn toggle_tag_selected(&mut self) {
+        if let Some(pid) = self.selected_process().map(|process| process.pid()) {
+            if self.tagged.remove(&pid).is_none() {
+                let start_time = self.system.process(pid).map_or(0, |p| p.start_time());
+                self.tagged.insert(pid, start_time);
+            }
+            self.table_state.select_next();
+        }
+    }
+
+    fn tag_visible(&mut self) {
+        let tags: Vec<_> = self
+            .visible_processes()
+            .iter()
+            // Threads are signalled through their process
+            .filter(|process| process.thread_kind() != Some(sysinfo::ThreadKind::Userland))
+            .map(|process| (process.pid(), process.start_time()))
+            .collect();
+        self.tagged.extend(tags);
+    }
+
+    fn tag_subtree(&mut self) {
+        if let Some(pid) = self.selected_process().map(|process| process.pid()) {
+            let processes = self.system.processes();
+            let tags = subtree(processes, pid)
+                .into_iter()
+                .filter_map(|pid| processes.get(&pid))
+                .filter(|process| process.thread_kind() != Some(sysinfo::ThreadKind::Userland))
+                .map(|process| (process.pid(), process.start_time()));
+            self.tagged.extend(tags);
+        }
+    }
+
+    /// Processes matching the kill-by-pattern input, sorted by PID.
+    fn pattern_matches(&self) -> std::result::Result<Vec<&sysinfo::Process>, String> {
+        let Some(pattern) = Pattern::new(self.pattern_mode, &self.pattern_input)? else {
+            return Ok(vec![]);
+        };
+        let own_pid = std::process::id();
+        let mut matches: Vec<_> = self
+            .system
+            .processes()
+            .values()
+            // Like pkill, match whole processes rather than their threads
+            .filter(|process| process.thread_kind() != Some(sysinfo::ThreadKind::Userland))
+            .filter(|process| {
+                process.pid().as_u32() != own_pid
+                    && pattern.matches(process, self.info.get(&process.pid()))
+            })
+            .collect();
+        matches.sort_by_key(|process| process.pid());
+        Ok(matches)
+    }
+
+    /// Hands the processes matching the pattern over to the kill modal.
+    /// Returns false (keeping the pattern modal open) if nothing matches.
+    fn prepare_pattern_kill(&mut self) -> bool {
+        let picked: Vec<_> = match self.pattern_matches() {
+            Ok(matches) => matches
+                .iter()
+                .map(|process| (process.pid(), process.start_time()))
+                .collect(),
+            Err(err) => {
+                self.set_status(format!("Invalid pattern: {}", err), true);
+                return false;
+            }
+        };
+        if picked.is_empty() {
+            self.set_status("No process matches the pattern".to_string(), true);
+            return false;
+        }
+        self.open_kill_modal(picked);
+        true
+    }
+
+    /// Hands the typed PIDs that still exist over to the kill modal, where the signal is
+    /// chosen and confirmed. Returns false (keeping the PID modal open) if nothing to kill.
+    fn prepare_kill_by_pid(&mut self) -> bool {
+        let pids = match parse_pid_list(&self.kill_by_pid_input) {
+            Ok(pids) if !pids.is_empty() => pids,
+            Ok(_) => return false,
+            Err(err) => {
+                self.set_status(err, true);
+                return false;
+            }
+        };
+        // The table may be up to a second old, a freshly started process would be missing
+        self.refresh_pids(&pids);
+        let (found, missing): (Vec<_>, Vec<_>) = pids
+            .into_iter()
+            .partition(|pid| self.system.process(*pid).is_some());
+        if found.is_empty() {
+            self.set_status("None of the PIDs exist".to_string(), true);
+            return false;
+        }
+        let picked = found
+            .into_iter()
+            .filter_map(|pid| Some((pid, self.system.process(pid)?.start_time())))
+            .collect();
+        self.open_kill_modal(picked);
+        if !missing.is_empty() {
+            let missing: Vec<_> = missing.iter().map(|pid| pid.to_string()).collect();
+            self.set_status(format!("No such process: {}", missing.join(", ")), true);
+        }
+        true
+    }
+
+    /// Lists the users that currently own at least one process.
+    fn open_user_picker(&mut self) {
+        let mut uids: Vec<u32> = self
+            .system
+            .processes()
+            .values()
+            .filter_map(|process| process.user_id().map(|uid| **uid))
+            .collect();
+        uids.sort_unstable();
+        uids.dedup();
+        let mut entries: Vec<(u32, String)> = uids
+            .into_iter()
+            .map(|uid| {
+                let name = get_user_by_uid(uid)
+                    .map(|u| u.name().to_string_lossy().to_string())
+                    .unwrap_or_else(|| uid.to_string());
+                (uid, name)
+            })
+            .collect();
+        entries.sort_by(|a, b| a.1.cmp(&b.1));
+        self.user_selected = self
+            .user_filter
+            .and_then(|uid| entries.iter().position(|(u, _)| *u == uid))
+            .map_or(0, |i| i + 1);
+        self.user_picker_entries = entries;
+        self.user_picker = true;
+    }
+
+    fn toggle_column(&mut self, column: Column) {
+        if self.columns.contains(&column) {
+            // An empty table would leave nothing to select
+            if self.columns.len() > 1 {
+                self.columns.retain(|c| *c != column);
+            }
+        } else {
+            self.columns.push(column);
+            self.columns
+                .sort_by_key(|c| Column::ALL.iter().position(|all| all == c));
+        }
+    }
+
+    fn set_search_query(&mut self, query: &str) {
+        self.textarea.select_all();
+        self.textarea.cut();
+        self.textarea.insert_str(query);
+    }
+
+    fn apply_preset(&mut self, index: usize) {
+        let Some(preset) = self.config.presets.get(index).cloned() else {
+            return;
+        };
+        self.set_search_query(&preset.query);
+        self.search_full = preset.full;
+        self.update_search_block();
+        self.sort_key = preset.sort;
+        self.sort_reverse = preset.reverse;
+        if !preset.columns.is_empty() {
+            self.columns = preset.columns;
+        }
+        self.table_state.select(Some(0));
+    }
+
+    fn save_preset(&mut self) {
+        let name = self.preset_name_input.trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+        let preset = Preset {
+            name,
+            query: self.textarea.lines().first().unwrap().clone(),
+            full: self.search_full,
+            sort: self.sort_key,
+            reverse: self.sort_reverse,
+            columns: self.columns.clone(),
+        };
+        match self
+            .config
+            .presets
+            .iter_mut()
+            .find(|p| p.name == preset.name)
+        {
+            Some(existing) => *existing = preset,
+            None => self.config.presets.push(preset),
+        }
+        if let Err(err) = self.config.save() {
+            self.set_status(format!("Could not save preset: {}", err), true);
+        }
+    }
+
+    /// Switches the MemMB column between RSS, PSS and USS. The config file keeps its own
+    /// default, which is what the next start shows.
+    fn cycle_memory_metric(&mut self) {
+        self.memory_metric = self.memory_metric.next();
+        self.set_status(
+            format!("Memory column shows {}", self.memory_metric.label()),
+            false,
+        );
     }
 
-    fn try_kill_by_pid(&mut self) {
-        if let Ok(pid_num) = self.kill_by_pid_input.parse::<u32>() {
-            let pid = sysinfo::Pid::from_u32(pid_num);
-            if let Some(process) = self.system.process(pid) {
-                let _ = process.kill_with(Signal::Kill);
+    fn remove_preset(&mut self, index: usize) {
+        if index < self.config.presets.len() {
+            self.config.presets.remove(index);
+            self.preset_selected = self
+                .preset_selected
+                .min(self.config.presets.len().saturating_sub(1));
+            if let Err(err) = self.config.save() {
+                self.set_status(format!("Could not remove preset: {}", err), true);
             }
         }
     }
@@ -578,3 +3525,101 @@ impl App {
         self.running = false;
     }
 }
+
+/// `0-3` or `0-3 (all threads)`.
+fn affinity_setting(cpus: &[usize], all_threads: bool) -> String {
+    if all_threads {
+        format!("{} (all threads)", sched::format_cpus(cpus))
+    } else {
+        sched::format_cpus(cpus)
+    }
+}
+
+/// `FIFO 10` for real-time policies, just the policy for the others.
+fn policy_setting(policy: sched::Policy, priority: i32) -> String {
+    if policy.is_realtime() {
+        format!("{} {}", policy.label(), priority)
+    } else {
+        policy.label().to_string()
+    }
+}
+
+fn search_block(full: bool) -> Block<'static> {
+    let scope = if full { "name, cmd, exe, cwd" } else { "name" };
+    Block::default()
+        .borders(ratatui::widgets::Borders::ALL)
+        .title(format!("Search (active) [{}]  [Tab] Toggle scope", scope))
+        .style(Style::default().fg(Color::Cyan))
+}
+
+/// Largest number of PIDs a single kill-by-PID entry may expand to.
+const MAX_PID_LIST: usize = 1024;
+
+/// Parses PIDs separated by spaces or commas, with `a-b` for inclusive ranges.
+fn parse_pid_list(input: &str) -> std::result::Result<Vec<sysinfo::Pid>, String> {
+    let mut pids = vec![];
+    for part in input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+    {
+        let parse = |s: &str| {
+            s.parse::<u32>()
+                .map_err(|_| format!("Invalid PID or range: {}", part))
+        };
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (parse(first)?, parse(last)?),
+            None => (parse(part)?, parse(part)?),
+        };
+        if first > last {
+            return Err(format!("Invalid range: {}", part));
+        }
+        if pids.len() + (last - first) as usize >= MAX_PID_LIST {
+            return Err(format!("Too many PIDs, at most {}", MAX_PID_LIST));
+        }
+        pids.extend((first..=last).map(sysinfo::Pid::from_u32));
+    }
+    pids.sort();
+    pids.dedup();
+    Ok(pids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pids(list: &[u32]) -> Vec<sysinfo::Pid> {
+        list.iter().copied().map(sysinfo::Pid::from_u32).collect()
+    }
+
+    #[test]
+    fn parses_pids_and_ranges() {
+        assert_eq!(parse_pid_list("1234"), Ok(pids(&[1234])));
+        assert_eq!(
+            parse_pid_list("1300-1302, 1400 12"),
+            Ok(pids(&[12, 1300, 1301, 1302, 1400]))
+        );
+        assert_eq!(parse_pid_list("  ,  "), Ok(vec![]));
+    }
+
+    #[test]
+    fn merges_duplicate_pids() {
+        assert_eq!(parse_pid_list("5 3-6,4"), Ok(pids(&[3, 4, 5, 6])));
+    }
+
+    #[test]
+    fn rejects_invalid_entries() {
+        assert!(parse_pid_list("12a").is_err());
+        assert!(parse_pid_list("-5").is_err());
+        assert!(parse_pid_list("1-2-3").is_err());
+        assert_eq!(
+            parse_pid_list("10-5"),
+            Err("Invalid range: 10-5".to_string())
+        );
+    }
+
+    #[test]
+    fn limits_the_number_of_pids() {
+        assert!(parse_pid_list(&format!("1-{}", MAX_PID_LIST)).is_ok());
+        assert!(parse_pid_list(&format!("1-{}", MAX_PID_LIST + 1)).is_err());
+    }
+}
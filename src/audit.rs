@@ -0,0 +1,112 @@
+//! Append-only log of the actions taken from procman, one JSON object per line.
+
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// One action on one process (or process group).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// Seconds since the epoch.
+    pub timestamp: u64,
+    /// Real UID of the operator running procman.
+    pub uid: u32,
+    pub pid: u32,
+    /// Start time of the target in seconds since the epoch, if known.
+    pub start_time: Option<u64>,
+    pub name: String,
+    pub cmdline: String,
+    /// What was done, e.g. `SIGTERM` or `killpg SIGKILL`.
+    pub action: String,
+    /// `ok` or the error that was reported.
+    pub result: String,
+}
+
+impl Entry {
+    pub fn now(pid: u32, action: String, result: &Result<(), String>) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs()),
+            uid: users::get_current_uid(),
+            pid,
+            start_time: None,
+            name: String::new(),
+            cmdline: String::new(),
+            action,
+            result: match result {
+                Ok(()) => "ok".to_string(),
+                Err(err) => err.clone(),
+            },
+        }
+    }
+}
+
+/// `$XDG_STATE_HOME/procman/audit.jsonl` (or `~/.local/state/procman/audit.jsonl`).
+pub fn default_path() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+    Some(dir.join("procman").join("audit.jsonl"))
+}
+
+pub fn append(path: &Path, entries: &[Entry]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut text = String::new();
+    for entry in entries {
+        text.push_str(&serde_json::to_string(entry)?);
+        text.push('\n');
+    }
+    // A single write per batch keeps lines of concurrent procman instances apart
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(text.as_bytes())
+}
+
+/// All entries in the log, oldest first. Lines that do not parse are skipped.
+pub fn read(path: &Path) -> std::io::Result<Vec<Entry>> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+    let mut entries = vec![];
+    for line in BufReader::new(file).lines() {
+        if let Ok(entry) = serde_json::from_str(&line?) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// `2024-05-01 13:37:00` in local time.
+pub fn format_time(timestamp: u64) -> String {
+    let time = timestamp as libc::time_t;
+    // SAFETY: localtime_r only writes to the tm it is given
+    let tm = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            return timestamp.to_string();
+        }
+        tm
+    };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
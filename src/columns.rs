@@ -0,0 +1,293 @@
+use std::cmp::Ordering;
+
+use ratatui::layout::Constraint;
+use serde::{Deserialize, Serialize};
+use sysinfo::Process;
+use users::get_user_by_uid;
+
+use crate::procfs::Info;
+
+/// A column of the process table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Column {
+    Pid,
+    Name,
+    User,
+    Cpu,
+    Mem,
+    Nice,
+    Priority,
+    Cpus,
+    Io,
+    Policy,
+    RtPriority,
+    Ports,
+    Command,
+}
+
+impl Column {
+    pub const ALL: [Column; 13] = [
+        Column::Pid,
+        Column::Name,
+        Column::User,
+        Column::Cpu,
+        Column::Mem,
+        Column::Nice,
+        Column::Priority,
+        Column::Cpus,
+        Column::Io,
+        Column::Policy,
+        Column::RtPriority,
+        Column::Ports,
+        Column::Command,
+    ];
+
+    pub fn default_columns() -> Vec<Column> {
+        vec![
+            Column::Pid,
+            Column::Name,
+            Column::User,
+            Column::Cpu,
+            Column::Mem,
+            Column::Command,
+        ]
+    }
+
+    pub fn header(self, metric: MemoryMetric) -> &'static str {
+        match self {
+            Column::Pid => "PID",
+            Column::Name => "Name",
+            Column::User => "User",
+            Column::Cpu => "CPU%",
+            Column::Mem => metric.header(),
+            Column::Nice => "Nice",
+            Column::Priority => "Prio",
+            Column::Cpus => "CPUs",
+            Column::Io => "IO",
+            Column::Policy => "Policy",
+            Column::RtPriority => "RT",
+            Column::Ports => "Ports",
+            Column::Command => "Command",
+        }
+    }
+
+    /// Fixed width of the column, `None` for the column that takes the remaining space.
+    pub fn width(self) -> Option<u16> {
+        match self {
+            Column::Pid => Some(10),
+            Column::Name => Some(20),
+            Column::User => Some(10),
+            Column::Cpu => Some(8),
+            Column::Mem => Some(10),
+            Column::Nice => Some(6),
+            Column::Priority => Some(6),
+            Column::Cpus => Some(12),
+            Column::Io => Some(6),
+            Column::Policy => Some(8),
+            Column::RtPriority => Some(4),
+            Column::Ports => Some(14),
+            Column::Command => None,
+        }
+    }
+
+    pub fn constraint(self) -> Constraint {
+        match self.width() {
+            Some(width) => Constraint::Max(width),
+            None => Constraint::Fill(1),
+        }
+    }
+
+    /// Text of the cell, `width` is only used by columns that truncate their content.
+    pub fn cell(
+        self,
+        process: &Process,
+        info: Option<&Info>,
+        metric: MemoryMetric,
+        width: usize,
+    ) -> String {
+        let show = |value: Option<i64>| value.map(|v| v.to_string()).unwrap_or_default();
+        match self {
+            Column::Pid => process.pid().to_string(),
+            Column::Name => process.name().to_string_lossy().to_string(),
+            Column::User => process_user(process),
+            Column::Cpu => format!("{:.1}%", process.cpu_usage()),
+            Column::Mem => memory(process, info, metric)
+                .map(|bytes| format!("{:.1}", bytes as f64 / 1024.0 / 1024.0))
+                .unwrap_or_default(),
+            Column::Nice => show(info.and_then(|info| info.nice)),
+            Column::Priority => show(info.and_then(|info| info.priority)),
+            Column::Cpus => info
+                .and_then(|info| info.affinity.as_deref())
+                .map(crate::sched::format_cpus)
+                .unwrap_or_default(),
+            Column::Policy => info
+                .and_then(|info| info.policy)
+                .map(|policy| policy.label().to_string())
+                .unwrap_or_default(),
+            Column::RtPriority => show(info.and_then(|info| info.rt_priority)),
+            Column::Io => info
+                .and_then(|info| info.io_priority)
+                .map(|priority| priority.to_string())
+                .unwrap_or_default(),
+            Column::Ports => info
+                .map(|info| {
+                    info.listening_ports
+                        .iter()
+                        .map(|port| port.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .unwrap_or_default(),
+            Column::Command => truncate_middle(&process_cmd(process), width),
+        }
+    }
+}
+
+/// Which memory figure the MemMB column shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryMetric {
+    /// Resident set size, counting shared pages fully in every process.
+    #[default]
+    Rss,
+    /// Proportional set size, shared pages divided among the processes sharing them.
+    Pss,
+    /// Unique set size, only the pages no other process maps.
+    Uss,
+}
+
+impl MemoryMetric {
+    pub fn label(self) -> &'static str {
+        match self {
+            MemoryMetric::Rss => "RSS",
+            MemoryMetric::Pss => "PSS",
+            MemoryMetric::Uss => "USS",
+        }
+    }
+
+    fn header(self) -> &'static str {
+        match self {
+            MemoryMetric::Rss => "MemMB",
+            MemoryMetric::Pss => "PssMB",
+            MemoryMetric::Uss => "UssMB",
+        }
+    }
+
+    pub fn next(self) -> MemoryMetric {
+        match self {
+            MemoryMetric::Rss => MemoryMetric::Pss,
+            MemoryMetric::Pss => MemoryMetric::Uss,
+            MemoryMetric::Uss => MemoryMetric::Rss,
+        }
+    }
+}
+
+/// Memory of `process` in bytes by `metric`, `None` if `smaps_rollup` could not be read.
+pub fn memory(process: &Process, info: Option<&Info>, metric: MemoryMetric) -> Option<u64> {
+    let usage = || info?.memory.as_ref();
+    match metric {
+        MemoryMetric::Rss => Some(process.memory()),
+        MemoryMetric::Pss => usage().map(|usage| usage.pss * 1024),
+        MemoryMetric::Uss => usage().map(|usage| usage.uss() * 1024),
+    }
+}
+
+/// What the process table is sorted by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    #[default]
+    Cpu,
+    Mem,
+    Pid,
+    Name,
+    User,
+}
+
+impl SortKey {
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Cpu => "CPU",
+            SortKey::Mem => "Mem",
+            SortKey::Pid => "PID",
+            SortKey::Name => "Name",
+            SortKey::User => "User",
+        }
+    }
+
+    pub fn next(self) -> SortKey {
+        match self {
+            SortKey::Cpu => SortKey::Mem,
+            SortKey::Mem => SortKey::Pid,
+            SortKey::Pid => SortKey::Name,
+            SortKey::Name => SortKey::User,
+            SortKey::User => SortKey::Cpu,
+        }
+    }
+
+    /// Natural order of the key: biggest consumers first for CPU and memory,
+    /// ascending for everything else. Ties are broken by PID to keep rows stable.
+    /// `memory` gives the figure the MemMB column shows.
+    pub fn compare(
+        self,
+        a: &Process,
+        b: &Process,
+        memory: impl Fn(&Process) -> Option<u64>,
+    ) -> Ordering {
+        let order = match self {
+            SortKey::Cpu => b
+                .cpu_usage()
+                .partial_cmp(&a.cpu_usage())
+                .unwrap_or(Ordering::Equal),
+            SortKey::Mem => memory(b).cmp(&memory(a)),
+            SortKey::Pid => Ordering::Equal,
+            SortKey::Name => a.name().cmp(b.name()),
+            SortKey::User => process_user(a).cmp(&process_user(b)),
+        };
+        order.then(a.pid().cmp(&b.pid()))
+    }
+}
+
+pub fn process_user(process: &Process) -> String {
+    process
+        .user_id()
+        .and_then(|uid| get_user_by_uid(**uid))
+        .map(|u| u.name().to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Kernel threads have neither an executable nor a command line and are children of kthreadd.
+pub fn is_kernel_thread(process: &Process) -> bool {
+    const KTHREADD: u32 = 2;
+    let parent_is_kthreadd = process.parent().map(|pid| pid.as_u32()) == Some(KTHREADD);
+    let is_kthreadd = process.pid().as_u32() == KTHREADD;
+    process.exe().is_none() && process.cmd().is_empty() && (parent_is_kthreadd || is_kthreadd)
+}
+
+pub fn process_cmd(process: &Process) -> String {
+    process
+        .cmd()
+        .iter()
+        .map(|s| s.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Shortens `s` to at most `max` characters by replacing its middle with an ellipsis,
+/// so both the program and its last arguments stay visible.
+pub fn truncate_middle(s: &str, max: usize) -> String {
+    let len = s.chars().count();
+    if len <= max {
+        return s.to_string();
+    }
+    if max < 3 {
+        return s.chars().take(max).collect();
+    }
+    let head = (max - 1) / 2;
+    let tail = max - 1 - head;
+    let mut out: String = s.chars().take(head).collect();
+    out.push('…');
+    out.extend(s.chars().skip(len - tail));
+    out
+}
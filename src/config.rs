@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use color_eyre::{eyre::WrapErr, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::columns::{Column, MemoryMetric, SortKey};
+use crate::protect::Rule;
+
+/// User configuration, read from `$XDG_CONFIG_HOME/procman/config.toml`
+/// (or `~/.config/procman/config.toml`). A missing file means defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub presets: Vec<Preset>,
+    /// Seconds to wait after SIGTERM before escalating to SIGKILL.
+    pub escalation_timeout: u64,
+    /// Processes that are skipped by bulk actions and need their name typed to be signalled.
+    pub protected: Vec<Rule>,
+    /// Where the audit log is written, `audit::default_path()` if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audit_log: Option<PathBuf>,
+    /// What the MemMB column shows.
+    pub memory_metric: MemoryMetric,
+    /// Hide the values of environment variables like `API_TOKEN` in the details panel.
+    pub mask_secrets: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            presets: vec![],
+            escalation_timeout: 10,
+            protected: vec![],
+            audit_log: None,
+            memory_metric: MemoryMetric::default(),
+            mask_secrets: true,
+        }
+    }
+}
+
+/// A named filter: search query plus the sort and column layout it was saved with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    #[serde(default)]
+    pub query: String,
+    /// Match the query against cmd, exe and cwd too.
+    #[serde(default)]
+    pub full: bool,
+    #[serde(default)]
+    pub sort: SortKey,
+    #[serde(default)]
+    pub reverse: bool,
+    #[serde(default = "Column::default_columns")]
+    pub columns: Vec<Column>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        let dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(dir.join("procman").join("config.toml"))
+    }
+
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text)
+                .wrap_err_with(|| format!("invalid config file {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => {
+                Err(err).wrap_err_with(|| format!("cannot read config file {}", path.display()))
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or_else(|| color_eyre::eyre::eyre!("no config directory"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, toml::to_string_pretty(self)?)
+            .wrap_err_with(|| format!("cannot write config file {}", path.display()))
+    }
+}
@@ -0,0 +1,547 @@
+//! Contents of the tabs of the process details panel.
+
+use std::collections::HashMap;
+use std::fs;
+
+use sysinfo::{Pid, Process};
+
+use crate::columns::process_user;
+use crate::net::{self, Protocol, Socket};
+use crate::procfs::{self, FdKind, Info};
+
+/// A tab of the details panel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Tab {
+    #[default]
+    Overview,
+    Command,
+    Environment,
+    Files,
+    Network,
+    Memory,
+    Limits,
+    Cgroup,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 8] = [
+        Tab::Overview,
+        Tab::Command,
+        Tab::Environment,
+        Tab::Files,
+        Tab::Network,
+        Tab::Memory,
+        Tab::Limits,
+        Tab::Cgroup,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Tab::Overview => "Overview",
+            Tab::Command => "Command",
+            Tab::Environment => "Environment",
+            Tab::Files => "Files",
+            Tab::Network => "Network",
+            Tab::Memory => "Memory",
+            Tab::Limits => "Limits",
+            Tab::Cgroup => "Cgroup/Namespaces",
+        }
+    }
+
+    fn index(self) -> usize {
+        Tab::ALL.iter().position(|tab| *tab == self).unwrap_or(0)
+    }
+
+    pub fn next(self) -> Tab {
+        Tab::ALL[(self.index() + 1) % Tab::ALL.len()]
+    }
+
+    pub fn prev(self) -> Tab {
+        Tab::ALL[(self.index() + Tab::ALL.len() - 1) % Tab::ALL.len()]
+    }
+}
+
+/// Text of a tab, one entry per line before wrapping.
+#[derive(Debug, Default, Clone)]
+pub struct Page {
+    /// Summary and column headings, always shown.
+    pub header: Vec<String>,
+    /// The lines the search of the panel filters.
+    pub rows: Vec<String>,
+}
+
+impl From<Vec<String>> for Page {
+    fn from(rows: Vec<String>) -> Self {
+        Self {
+            header: vec![],
+            rows,
+        }
+    }
+}
+
+/// With `mask_secrets` the values of environment variables that look like secrets are hidden.
+pub fn page(tab: Tab, process: &Process, info: Option<&Info>, mask_secrets: bool) -> Page {
+    let pid = process.pid();
+    match tab {
+        Tab::Overview => overview(process, info).into(),
+        Tab::Command => command(process).into(),
+        Tab::Environment => environment(pid, mask_secrets),
+        Tab::Files => files(pid),
+        Tab::Network => network(pid),
+        Tab::Memory => memory(process),
+        Tab::Limits => match read(pid, "limits") {
+            // The first line holds the column headings
+            Ok(limits) => {
+                let mut rows = to_lines(limits);
+                Page {
+                    header: rows.drain(..rows.len().min(1)).collect(),
+                    rows,
+                }
+            }
+            Err(err) => vec![err].into(),
+        },
+        Tab::Cgroup => cgroup(pid).into(),
+    }
+}
+
+/// Contents of `/proc/<pid>/<file>`, or a line explaining why it cannot be read.
+/// Tabs are replaced because the terminal would not overwrite the cells they skip.
+fn read(pid: Pid, file: &str) -> Result<String, String> {
+    fs::read_to_string(format!("/proc/{}/{}", pid, file))
+        .map(|text| text.replace('\t', " "))
+        .map_err(|err| format!("Cannot read /proc/{}/{}: {}", pid, file, err))
+}
+
+fn to_lines(text: String) -> Vec<String> {
+    text.lines().map(str::to_string).collect()
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.2} MB", bytes as f64 / 1024.0 / 1024.0)
+}
+
+/// `3d 04:05:06`, without the days when shorter than a day.
+fn format_duration(seconds: u64) -> String {
+    let time = format!(
+        "{:02}:{:02}:{:02}",
+        seconds % 86400 / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    );
+    match seconds / 86400 {
+        0 => time,
+        days => format!("{}d {}", days, time),
+    }
+}
+
+fn overview(process: &Process, info: Option<&Info>) -> Vec<String> {
+    let show = |value: Option<i64>| value.map_or("-".to_string(), |v| v.to_string());
+    let path = |path: Option<&std::path::Path>| {
+        path.map_or("Unknown".to_string(), |p| p.display().to_string())
+    };
+    let disk_usage = process.disk_usage();
+    vec![
+        format!("PID:               {}", process.pid()),
+        format!("Name:              {}", process.name().to_string_lossy()),
+        format!(
+            "Parent PID:        {}",
+            process
+                .parent()
+                .map_or("-".to_string(), |pid| pid.to_string())
+        ),
+        format!("User:              {}", process_user(process)),
+        format!("Status:            {}", process.status()),
+        format!("Executable:        {}", path(process.exe())),
+        format!("Working Directory: {}", path(process.cwd())),
+        format!(
+            "Start Time:        {}",
+            crate::audit::format_time(process.start_time())
+        ),
+        format!("Run Time:          {}", format_duration(process.run_time())),
+        format!(
+            "Threads:           {}",
+            crate::procfs::threads(process.pid()).len()
+        ),
+        String::new(),
+        format!("CPU:               {:.1}%", process.cpu_usage()),
+        format!("Nice:              {}", show(info.and_then(|i| i.nice))),
+        format!("Priority:          {}", show(info.and_then(|i| i.priority))),
+        format!(
+            "Policy:            {}",
+            info.and_then(|i| i.policy)
+                .map_or("-", |policy| policy.label())
+        ),
+        format!(
+            "RT Priority:       {}",
+            show(info.and_then(|i| i.rt_priority))
+        ),
+        format!(
+            "CPU Affinity:      {}",
+            info.and_then(|i| i.affinity.as_deref())
+                .map_or("-".to_string(), crate::sched::format_cpus)
+        ),
+        format!(
+            "I/O Priority:      {}",
+            info.and_then(|i| i.io_priority)
+                .map_or("-".to_string(), |priority| priority.to_string())
+        ),
+        String::new(),
+        format!("Physical Memory:   {}", megabytes(process.memory())),
+        format!("Virtual Memory:    {}", megabytes(process.virtual_memory())),
+        format!(
+            "Disk Read:         {}",
+            megabytes(disk_usage.total_read_bytes)
+        ),
+        format!(
+            "Disk Written:      {}",
+            megabytes(disk_usage.total_written_bytes)
+        ),
+    ]
+}
+
+fn command(process: &Process) -> Vec<String> {
+    let mut lines = vec!["Command line:".to_string()];
+    if process.cmd().is_empty() {
+        lines.push("  (none, kernel thread or not readable)".to_string());
+    }
+    for (i, arg) in process.cmd().iter().enumerate() {
+        lines.push(format!("  [{}] {}", i, arg.to_string_lossy()));
+    }
+    lines
+}
+
+/// Whether the variable probably holds a credential, judged by its name.
+fn is_secret(name: &str) -> bool {
+    let name = name.to_uppercase();
+    ["TOKEN", "PASSWORD", "PASSWD", "SECRET", "KEY"]
+        .iter()
+        .any(|word| name.contains(word))
+}
+
+fn environment(pid: Pid, mask_secrets: bool) -> Page {
+    let mut vars = match procfs::environ(pid) {
+        Ok(vars) => vars,
+        Err(err) => return vec![format!("Cannot read /proc/{}/environ: {}", pid, err)].into(),
+    };
+    vars.sort();
+    let masked = vars.iter().filter(|(name, _)| is_secret(name)).count();
+    let summary = match (mask_secrets, masked) {
+        (_, 0) => format!("{} variables", vars.len()),
+        (true, _) => format!(
+            "{} variables, values of {} that look like secrets are masked ([m] to show)",
+            vars.len(),
+            masked
+        ),
+        (false, _) => format!(
+            "{} variables, {} that look like secrets are shown ([m] to mask)",
+            vars.len(),
+            masked
+        ),
+    };
+    Page {
+        header: vec![summary, String::new()],
+        rows: vars
+            .iter()
+            .map(|(name, value)| {
+                if mask_secrets && is_secret(name) && !value.is_empty() {
+                    format!("{}=********", name)
+                } else {
+                    format!("{}={}", name, value)
+                }
+            })
+            .collect(),
+    }
+}
+
+fn files(pid: Pid) -> Page {
+    let fds = match procfs::fds(pid) {
+        Ok(fds) => fds,
+        Err(err) => return vec![format!("Cannot read /proc/{}/fd: {}", pid, err)].into(),
+    };
+    let counts: Vec<_> = [
+        FdKind::File,
+        FdKind::Socket,
+        FdKind::Pipe,
+        FdKind::EventFd,
+        FdKind::AnonInode,
+        FdKind::Other,
+    ]
+    .into_iter()
+    .filter_map(|kind| {
+        let count = fds.iter().filter(|fd| fd.kind == kind).count();
+        (count > 0).then(|| format!("{} {}", count, kind.label()))
+    })
+    .collect();
+    let mut summary = format!("{} open file descriptors", fds.len());
+    if !counts.is_empty() {
+        summary = format!("{}: {}", summary, counts.join(", "));
+    }
+    Page {
+        header: vec![
+            summary,
+            String::new(),
+            format!(
+                "{:>6}  {:<10} {:>12}  {:<22} {}",
+                "FD", "Type", "Pos", "Flags", "Target"
+            ),
+        ],
+        rows: fds
+            .iter()
+            .map(|fd| {
+                format!(
+                    "{:>6}  {:<10} {:>12}  {:<22} {}",
+                    fd.fd,
+                    fd.kind.label(),
+                    fd.pos.map_or("-".to_string(), |pos| pos.to_string()),
+                    fd.flags.map_or("-".to_string(), procfs::format_open_flags),
+                    fd.target
+                )
+            })
+            .collect(),
+    }
+}
+
+fn network(pid: Pid) -> Page {
+    let fds = match procfs::fds(pid) {
+        Ok(fds) => fds,
+        Err(err) => return vec![format!("Cannot read /proc/{}/fd: {}", pid, err)].into(),
+    };
+    // The tables of the process's own network namespace
+    let mut sockets: HashMap<u64, Socket> = net::sockets(&format!("/proc/{}/net", pid))
+        .into_iter()
+        .map(|socket| (socket.inode, socket))
+        .collect();
+    let mut owned = vec![];
+    let mut other = vec![];
+    for fd in &fds {
+        let Some(inode) = fd.socket_inode() else {
+            continue;
+        };
+        match sockets.remove(&inode) {
+            Some(socket) => owned.push((fd.fd, socket)),
+            // Netlink, packet and other sockets have no table here
+            None => other.push((fd.fd, inode)),
+        }
+    }
+
+    let mut counts: Vec<_> = Protocol::ALL
+        .into_iter()
+        .filter_map(|protocol| {
+            let count = owned.iter().filter(|(_, s)| s.protocol == protocol).count();
+            (count > 0).then(|| format!("{} {}", count, protocol.label()))
+        })
+        .collect();
+    if !other.is_empty() {
+        counts.push(format!("{} other", other.len()));
+    }
+    let mut summary = format!("{} sockets", owned.len() + other.len());
+    if !counts.is_empty() {
+        summary = format!("{}: {}", summary, counts.join(", "));
+    }
+    let mut listening: Vec<_> = owned
+        .iter()
+        .filter(|(_, socket)| socket.is_listening())
+        .filter_map(|(_, socket)| {
+            let port = socket.local?.port();
+            Some(format!("{}/{}", port, socket.protocol.label()))
+        })
+        .collect();
+    listening.sort();
+    listening.dedup();
+    let listening = if listening.is_empty() {
+        "-".to_string()
+    } else {
+        listening.join(", ")
+    };
+
+    let row = |fd: &str,
+               protocol: &str,
+               state: &str,
+               send: &str,
+               recv: &str,
+               local: &str,
+               remote: &str| {
+        format!(
+            "{:>6}  {:<6} {:<12} {:>8} {:>8}  {:<40} {}",
+            fd, protocol, state, send, recv, local, remote
+        )
+    };
+    let mut rows: Vec<_> = owned
+        .iter()
+        .map(|(fd, socket)| {
+            let (send, recv) = socket
+                .queues
+                .map_or(("-".to_string(), "-".to_string()), |(tx, rx)| {
+                    (tx.to_string(), rx.to_string())
+                });
+            row(
+                &fd.to_string(),
+                socket.protocol.label(),
+                socket.state,
+                &send,
+                &recv,
+                &socket.local_label(),
+                &socket.remote_label(),
+            )
+        })
+        .collect();
+    rows.extend(other.iter().map(|(fd, inode)| {
+        row(
+            &fd.to_string(),
+            "other",
+            "-",
+            "-",
+            "-",
+            &format!("socket:[{}]", inode),
+            "-",
+        )
+    }));
+    Page {
+        header: vec![
+            summary,
+            format!("Listening on: {}", listening),
+            String::new(),
+            row(
+                "FD", "Proto", "State", "Send-Q", "Recv-Q", "Local", "Remote",
+            ),
+        ],
+        rows,
+    }
+}
+
+/// `512 kB`, `12.3 MB` or `1.50 GB`.
+fn kilobytes(kb: u64) -> String {
+    if kb >= 1024 * 1024 {
+        format!("{:.2} GB", kb as f64 / 1024.0 / 1024.0)
+    } else if kb >= 1024 {
+        format!("{:.1} MB", kb as f64 / 1024.0)
+    } else {
+        format!("{} kB", kb)
+    }
+}
+
+fn memory(process: &Process) -> Page {
+    let pid = process.pid();
+    let rollup = match procfs::smaps_rollup(pid) {
+        Ok(rollup) => rollup,
+        Err(err) => {
+            return vec![
+                format!("Resident (RSS): {}", megabytes(process.memory())),
+                format!("Virtual:        {}", megabytes(process.virtual_memory())),
+                String::new(),
+                format!("Cannot read /proc/{}/smaps_rollup: {}", pid, err),
+            ]
+            .into()
+        }
+    };
+    let mut header = vec![
+        format!("Resident (RSS):      {}", kilobytes(rollup.rss)),
+        format!(
+            "Proportional (PSS):  {}  (anon {}, file {}, shmem {})",
+            kilobytes(rollup.pss),
+            kilobytes(rollup.pss_anon),
+            kilobytes(rollup.pss_file),
+            kilobytes(rollup.pss_shmem)
+        ),
+        format!("Unique (USS):        {}", kilobytes(rollup.uss())),
+        format!(
+            "Shared clean/dirty:  {} / {}",
+            kilobytes(rollup.shared_clean),
+            kilobytes(rollup.shared_dirty)
+        ),
+        format!(
+            "Private clean/dirty: {} / {}",
+            kilobytes(rollup.private_clean),
+            kilobytes(rollup.private_dirty)
+        ),
+        format!(
+            "Swap:                {}  (PSS {})",
+            kilobytes(rollup.swap),
+            kilobytes(rollup.swap_pss)
+        ),
+        format!(
+            "Virtual:             {}",
+            megabytes(process.virtual_memory())
+        ),
+        String::new(),
+    ];
+
+    let mappings = match procfs::smaps(pid) {
+        Ok(mappings) => mappings,
+        Err(err) => {
+            header.push(format!("Cannot read /proc/{}/smaps: {}", pid, err));
+            return Page {
+                header,
+                rows: vec![],
+            };
+        }
+    };
+    let mut files: HashMap<String, (usize, procfs::MemoryUsage)> = HashMap::new();
+    for (path, usage) in &mappings {
+        let (count, total) = files.entry(path.clone()).or_default();
+        *count += 1;
+        total.add(usage);
+    }
+    let mut files: Vec<_> = files.into_iter().collect();
+    files.sort_by(|(a_path, (_, a)), (b_path, (_, b))| {
+        b.pss.cmp(&a.pss).then_with(|| a_path.cmp(b_path))
+    });
+    header.push(format!(
+        "{} mappings of {} files, largest PSS first",
+        mappings.len(),
+        files.len()
+    ));
+    let row = |rss: &str, pss: &str, uss: &str, swap: &str, count: &str, file: &str| {
+        format!(
+            "{:>10} {:>10} {:>10} {:>10} {:>5}  {}",
+            rss, pss, uss, swap, count, file
+        )
+    };
+    header.push(row("RSS", "PSS", "USS", "Swap", "Maps", "File"));
+    Page {
+        header,
+        rows: files
+            .iter()
+            .map(|(path, (count, usage))| {
+                row(
+                    &kilobytes(usage.rss),
+                    &kilobytes(usage.pss),
+                    &kilobytes(usage.uss()),
+                    &kilobytes(usage.swap),
+                    &count.to_string(),
+                    path,
+                )
+            })
+            .collect(),
+    }
+}
+
+fn cgroup(pid: Pid) -> Vec<String> {
+    let mut lines = vec!["Cgroups:".to_string()];
+    match read(pid, "cgroup") {
+        Ok(cgroup) => lines.extend(cgroup.lines().map(|line| format!("  {}", line))),
+        Err(err) => lines.push(format!("  {}", err)),
+    }
+    lines.push(String::new());
+    lines.push("Namespaces:".to_string());
+    match fs::read_dir(format!("/proc/{}/ns", pid)) {
+        Ok(entries) => {
+            let mut namespaces: Vec<_> = entries
+                .filter_map(|entry| {
+                    let entry = entry.ok()?;
+                    let target = fs::read_link(entry.path())
+                        .map_or("not readable".to_string(), |t| t.display().to_string());
+                    Some(format!(
+                        "  {:<18} {}",
+                        entry.file_name().to_string_lossy(),
+                        target
+                    ))
+                })
+                .collect();
+            namespaces.sort();
+            lines.extend(namespaces);
+        }
+        Err(err) => lines.push(format!("  Cannot read /proc/{}/ns: {}", pid, err)),
+    }
+    lines
+}
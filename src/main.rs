@@ -1,14 +1,27 @@
 pub use app::App;
 
 pub mod app;
+pub mod audit;
+pub mod columns;
+pub mod config;
+pub mod details;
+pub mod net;
+pub mod procfs;
+pub mod protect;
+pub mod query;
+pub mod sched;
+pub mod signals;
+pub mod tree;
 
 fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
+    let config = config::Config::load()?;
+    let protection = protect::Protection::new(&config.protected)?;
     let terminal = ratatui::init();
 
     crossterm::execute!(std::io::stdout(), crossterm::event::EnableMouseCapture)?;
 
-    let result = App::new().run(terminal);
+    let result = App::new(config, protection).run(terminal);
 
     crossterm::execute!(std::io::stdout(), crossterm::event::DisableMouseCapture)?;
 
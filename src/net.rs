@@ -0,0 +1,335 @@
+//! Sockets from the `/proc/net` tables, which are matched to processes by inode.
+
+use std::collections::HashMap;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use sysinfo::Pid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Tcp,
+    Tcp6,
+    Udp,
+    Udp6,
+    Unix,
+}
+
+impl Protocol {
+    pub const ALL: [Protocol; 5] = [
+        Protocol::Tcp,
+        Protocol::Tcp6,
+        Protocol::Udp,
+        Protocol::Udp6,
+        Protocol::Unix,
+    ];
+
+    /// Also the name of the table in `/proc/net`.
+    pub fn label(self) -> &'static str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Tcp6 => "tcp6",
+            Protocol::Udp => "udp",
+            Protocol::Udp6 => "udp6",
+            Protocol::Unix => "unix",
+        }
+    }
+
+    fn is_udp(self) -> bool {
+        matches!(self, Protocol::Udp | Protocol::Udp6)
+    }
+}
+
+/// A row of one of the `/proc/net` tables.
+#[derive(Debug, Clone)]
+pub struct Socket {
+    pub protocol: Protocol,
+    pub inode: u64,
+    /// `None` for Unix sockets.
+    pub local: Option<SocketAddr>,
+    pub remote: Option<SocketAddr>,
+    /// Bound path of a Unix socket, empty when unnamed.
+    pub path: String,
+    /// `LISTEN`, `ESTABLISHED`, ... like ss(8) shows them.
+    pub state: &'static str,
+    /// Send and receive queue sizes in bytes, TCP and UDP only.
+    pub queues: Option<(u64, u64)>,
+}
+
+impl Socket {
+    /// TCP sockets accepting connections and bound, unconnected UDP sockets.
+    pub fn is_listening(&self) -> bool {
+        self.state == "LISTEN" || (self.protocol.is_udp() && self.state == "UNCONN")
+    }
+
+    pub fn local_label(&self) -> String {
+        match self.local {
+            Some(addr) => addr.to_string(),
+            None if self.path.is_empty() => "-".to_string(),
+            None => self.path.clone(),
+        }
+    }
+
+    pub fn remote_label(&self) -> String {
+        match self.remote {
+            Some(addr) if addr.port() == 0 && addr.ip().is_unspecified() => "*".to_string(),
+            Some(addr) => addr.to_string(),
+            None => "-".to_string(),
+        }
+    }
+}
+
+/// Every socket in the tables under `dir`, e.g. `/proc/net` or `/proc/<pid>/net` for the
+/// network namespace of a process. Tables that cannot be read are skipped.
+pub fn sockets(dir: &str) -> Vec<Socket> {
+    let mut sockets = vec![];
+    for protocol in Protocol::ALL {
+        let Ok(table) = fs::read_to_string(format!("{}/{}", dir, protocol.label())) else {
+            continue;
+        };
+        // The first line holds the column headings
+        sockets.extend(table.lines().skip(1).filter_map(|line| match protocol {
+            Protocol::Unix => parse_unix(line),
+            _ => parse_inet(protocol, line),
+        }));
+    }
+    sockets
+}
+
+/// TCP and UDP sockets of this network namespace, by inode.
+pub fn inet_sockets() -> HashMap<u64, Socket> {
+    sockets("/proc/net")
+        .into_iter()
+        .filter(|socket| socket.local.is_some())
+        .map(|socket| (socket.inode, socket))
+        .collect()
+}
+
+/// Local ports of the sockets `pid` has open, and the ports among them it listens on,
+/// given the result of `inet_sockets`.
+pub fn ports_of(pid: Pid, sockets: &HashMap<u64, Socket>) -> (Vec<u16>, Vec<u16>) {
+    let mut local = vec![];
+    let mut listening = vec![];
+    for inode in crate::procfs::socket_inodes(pid) {
+        let Some(socket) = sockets.get(&inode) else {
+            continue;
+        };
+        let Some(addr) = socket.local else {
+            continue;
+        };
+        local.push(addr.port());
+        if socket.is_listening() {
+            listening.push(addr.port());
+        }
+    }
+    // A port bound for both IPv4 and IPv6 is listed once
+    for ports in [&mut local, &mut listening] {
+        ports.sort();
+        ports.dedup();
+    }
+    (local, listening)
+}
+
+/// Processes with a TCP or UDP socket bound to local `port` in this network namespace,
+/// found by walking the fd tables in `/proc`. Processes whose fds cannot be read are missed.
+pub fn port_owners(port: u16) -> Vec<(Pid, Socket)> {
+    let sockets: HashMap<_, _> = inet_sockets()
+        .into_iter()
+        .filter(|(_, socket)| socket.local.is_some_and(|addr| addr.port() == port))
+        .collect();
+    if sockets.is_empty() {
+        return vec![];
+    }
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return vec![];
+    };
+    let mut pids: Vec<Pid> = entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        .collect();
+    pids.sort();
+    let mut owners = vec![];
+    for pid in pids {
+        for inode in crate::procfs::socket_inodes(pid) {
+            if let Some(socket) = sockets.get(&inode) {
+                owners.push((pid, socket.clone()));
+            }
+        }
+    }
+    owners
+}
+
+/// `  0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 ... inode ...`
+fn parse_inet(protocol: Protocol, line: &str) -> Option<Socket> {
+    let fields: Vec<_> = line.split_whitespace().collect();
+    let state = u8::from_str_radix(fields.get(3)?, 16).ok()?;
+    let (tx, rx) = fields.get(4)?.split_once(':')?;
+    Some(Socket {
+        protocol,
+        inode: fields.get(9)?.parse().ok()?,
+        local: parse_address(fields.get(1)?),
+        remote: parse_address(fields.get(2)?),
+        path: String::new(),
+        state: if protocol.is_udp() {
+            // UDP reuses the TCP numbers, only these two occur
+            match state {
+                0x01 => "ESTAB",
+                _ => "UNCONN",
+            }
+        } else {
+            tcp_state(state)
+        },
+        queues: Some((
+            u64::from_str_radix(tx, 16).ok()?,
+            u64::from_str_radix(rx, 16).ok()?,
+        )),
+    })
+}
+
+/// `0100007F:1F90` or the IPv6 equivalent. The address is printed as 32-bit words in
+/// host byte order, the port in the usual order.
+fn parse_address(field: &str) -> Option<SocketAddr> {
+    let (address, port) = field.split_once(':')?;
+    let mut bytes = vec![];
+    for i in (0..address.len()).step_by(8) {
+        let word = u32::from_str_radix(address.get(i..i + 8)?, 16).ok()?;
+        bytes.extend(word.to_ne_bytes());
+    }
+    let ip = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?)),
+        16 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, u16::from_str_radix(port, 16).ok()?))
+}
+
+fn tcp_state(state: u8) -> &'static str {
+    match state {
+        0x01 => "ESTABLISHED",
+        0x02 => "SYN_SENT",
+        0x03 => "SYN_RECV",
+        0x04 => "FIN_WAIT1",
+        0x05 => "FIN_WAIT2",
+        0x06 => "TIME_WAIT",
+        0x07 => "CLOSE",
+        0x08 => "CLOSE_WAIT",
+        0x09 => "LAST_ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        _ => "UNKNOWN",
+    }
+}
+
+/// `0000000020c23135: 00000002 00000000 00010000 0001 01 1125 /run/some.sock`
+fn parse_unix(line: &str) -> Option<Socket> {
+    // __SO_ACCEPTCON, set on sockets that listen()
+    const ACCEPTCON: u32 = 0x10000;
+    let fields: Vec<_> = line.split_whitespace().collect();
+    let flags = u32::from_str_radix(fields.get(3)?, 16).ok()?;
+    let state = match *fields.get(5)? {
+        _ if flags & ACCEPTCON != 0 => "LISTEN",
+        "01" => "UNCONN",
+        "02" => "CONNECTING",
+        "03" => "CONNECTED",
+        "04" => "DISCONNECTING",
+        _ => "UNKNOWN",
+    };
+    Some(Socket {
+        protocol: Protocol::Unix,
+        inode: fields.get(6)?.parse().ok()?,
+        local: None,
+        remote: None,
+        path: fields
+            .get(7..)
+            .map(|path| path.join(" "))
+            .unwrap_or_default(),
+        state,
+        queues: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // /proc/net prints addresses in host byte order, these lines come from a little-endian machine
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn parses_tcp_listen_line() {
+        let line = "   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 12345 1 0000000000000000 100 0 0 10 0";
+        let socket = parse_inet(Protocol::Tcp, line).unwrap();
+        assert_eq!(socket.inode, 12345);
+        assert_eq!(socket.local, Some("127.0.0.1:8080".parse().unwrap()));
+        assert_eq!(socket.remote_label(), "*");
+        assert_eq!(socket.state, "LISTEN");
+        assert_eq!(socket.queues, Some((0, 0)));
+        assert!(socket.is_listening());
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn parses_tcp_established_line() {
+        let line = "   1: 0F02000A:9C40 2E2A1E8E:01BB 01 00000010:00000020 02:000AF9A5 00000000  1000        0 67890 2 0000000000000000 20 4 30 10 -1";
+        let socket = parse_inet(Protocol::Tcp, line).unwrap();
+        assert_eq!(socket.local, Some("10.0.2.15:40000".parse().unwrap()));
+        assert_eq!(socket.remote, Some("142.30.42.46:443".parse().unwrap()));
+        assert_eq!(socket.state, "ESTABLISHED");
+        assert_eq!(socket.queues, Some((16, 32)));
+        assert!(!socket.is_listening());
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn parses_tcp6_line() {
+        let line = "   0: 000080FE000000000000000001000000:0016 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 4242 1 0000000000000000 100 0 0 10 0";
+        let socket = parse_inet(Protocol::Tcp6, line).unwrap();
+        assert_eq!(socket.local, Some("[fe80::1]:22".parse().unwrap()));
+        assert_eq!(socket.remote_label(), "*");
+        assert_eq!(socket.state, "LISTEN");
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn parses_udp_lines() {
+        let unconnected = "  123: 00000000:0044 00000000:0000 07 00000000:00000000 00:00000000 00000000     0        0 2345 2 0000000000000000 0";
+        let socket = parse_inet(Protocol::Udp, unconnected).unwrap();
+        assert_eq!(socket.local, Some("0.0.0.0:68".parse().unwrap()));
+        assert_eq!(socket.state, "UNCONN");
+        assert!(socket.is_listening());
+
+        let connected = "  124: 0100007F:D431 0100007F:0035 01 00000000:00000000 00:00000000 00000000   101        0 2346 2 0000000000000000 0";
+        let socket = parse_inet(Protocol::Udp, connected).unwrap();
+        assert_eq!(socket.remote, Some("127.0.0.1:53".parse().unwrap()));
+        assert_eq!(socket.state, "ESTAB");
+        assert!(!socket.is_listening());
+    }
+
+    #[test]
+    fn skips_inet_header() {
+        let header = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode";
+        assert!(parse_inet(Protocol::Tcp, header).is_none());
+    }
+
+    #[test]
+    fn parses_unix_lines() {
+        let listening = "0000000020c23135: 00000002 00000000 00010000 0001 01 1125 /run/some.sock";
+        let socket = parse_unix(listening).unwrap();
+        assert_eq!(socket.inode, 1125);
+        assert_eq!(socket.state, "LISTEN");
+        assert_eq!(socket.local_label(), "/run/some.sock");
+
+        let connected = "00000000e2d5c1a9: 00000003 00000000 00000000 0001 03 20001";
+        let socket = parse_unix(connected).unwrap();
+        assert_eq!(socket.state, "CONNECTED");
+        assert_eq!(socket.local_label(), "-");
+
+        let spaced = "00000000e2d5c1aa: 00000002 00000000 00000000 0002 01 20002 /tmp/my socket";
+        let socket = parse_unix(spaced).unwrap();
+        assert_eq!(socket.state, "UNCONN");
+        assert_eq!(socket.path, "/tmp/my socket");
+    }
+
+    #[test]
+    fn skips_unix_header() {
+        assert!(parse_unix("Num       RefCount Protocol Flags    Type St Inode Path").is_none());
+    }
+}
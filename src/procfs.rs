@@ -0,0 +1,418 @@
+//! Readers for `/proc/<pid>` files that sysinfo does not expose.
+
+use std::fs;
+
+use sysinfo::Pid;
+
+/// Fields of `/proc/<pid>/stat` after the command name, so that index 0 is the state
+/// (field 3 in proc(5)). The name is skipped because it may contain spaces and parentheses.
+fn stat_fields(pid: Pid) -> Option<Vec<String>> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let rest = &stat[stat.rfind(')')? + 1..];
+    Some(rest.split_whitespace().map(str::to_string).collect())
+}
+
+/// Start time of the process in seconds since the epoch, computed the same way as
+/// `sysinfo::Process::start_time` so the two can be compared.
+pub fn start_time(pid: Pid) -> Option<u64> {
+    // starttime is field 22, in clock ticks since boot
+    let ticks: u64 = stat_fields(pid)?.get(19)?.parse().ok()?;
+    // SAFETY: sysconf has no memory safety requirements
+    let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks_per_second <= 0 {
+        return None;
+    }
+    Some(sysinfo::System::boot_time() + ticks / ticks_per_second as u64)
+}
+
+/// Process group ID (field 5), `None` for kernel threads, which report group 0.
+pub fn process_group(pid: Pid) -> Option<Pid> {
+    id_field(pid, 2)
+}
+
+/// Session ID (field 6), `None` for kernel threads, which report session 0.
+pub fn session(pid: Pid) -> Option<Pid> {
+    id_field(pid, 3)
+}
+
+fn id_field(pid: Pid, index: usize) -> Option<Pid> {
+    let id: u32 = stat_fields(pid)?.get(index)?.parse().ok()?;
+    (id != 0).then(|| Pid::from_u32(id))
+}
+
+/// Per-process values that the table shows besides what sysinfo provides,
+/// read once per refresh.
+#[derive(Debug, Default, Clone)]
+pub struct Info {
+    /// Kernel priority (field 18), 20 + nice for normal processes.
+    pub priority: Option<i64>,
+    /// Nice value of the main thread (field 19).
+    pub nice: Option<i64>,
+    /// CPUs the main thread may run on.
+    pub affinity: Option<Vec<usize>>,
+    /// I/O priority of the main thread.
+    pub io_priority: Option<crate::sched::IoPriority>,
+    /// Real-time priority (field 40), 0 for normal policies.
+    pub rt_priority: Option<i64>,
+    /// Scheduling policy (field 41).
+    pub policy: Option<crate::sched::Policy>,
+    /// TCP and UDP ports the process listens on. This and `local_ports` are only filled
+    /// while the Ports column is shown or a query has a `port:` term.
+    pub listening_ports: Vec<u16>,
+    /// Local ports of all TCP and UDP sockets of the process, listening or not.
+    pub local_ports: Vec<u16>,
+    /// `smaps_rollup` totals, only read while the MemMB column shows PSS or USS.
+    pub memory: Option<MemoryUsage>,
+}
+
+pub fn info(pid: Pid) -> Info {
+    let fields = stat_fields(pid);
+    let field = |index: usize| fields.as_ref()?.get(index)?.parse().ok();
+    Info {
+        priority: field(15),
+        nice: field(16),
+        affinity: crate::sched::affinity(pid).ok(),
+        io_priority: crate::sched::io_priority(pid).ok(),
+        rt_priority: field(37),
+        policy: field(38).and_then(crate::sched::Policy::from_raw),
+        listening_ports: vec![],
+        local_ports: vec![],
+        memory: None,
+    }
+}
+
+/// Thread IDs of `pid` from `/proc/<pid>/task`, just `pid` if they cannot be listed.
+pub fn threads(pid: Pid) -> Vec<Pid> {
+    let Ok(entries) = fs::read_dir(format!("/proc/{}/task", pid)) else {
+        return vec![pid];
+    };
+    let mut tids: Vec<_> = entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        .map(Pid::from_u32)
+        .collect();
+    tids.sort();
+    tids
+}
+
+/// What an open file descriptor refers to, from the target of its `/proc/<pid>/fd` link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FdKind {
+    File,
+    Socket,
+    Pipe,
+    EventFd,
+    AnonInode,
+    Other,
+}
+
+impl FdKind {
+    fn from_target(target: &str) -> Self {
+        if target.starts_with('/') {
+            FdKind::File
+        } else if target.starts_with("socket:") {
+            FdKind::Socket
+        } else if target.starts_with("pipe:") {
+            FdKind::Pipe
+        } else if target == "anon_inode:[eventfd]" {
+            FdKind::EventFd
+        } else if target.starts_with("anon_inode:") {
+            FdKind::AnonInode
+        } else {
+            FdKind::Other
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FdKind::File => "file",
+            FdKind::Socket => "socket",
+            FdKind::Pipe => "pipe",
+            FdKind::EventFd => "eventfd",
+            FdKind::AnonInode => "anon_inode",
+            FdKind::Other => "other",
+        }
+    }
+}
+
+/// An open file descriptor of a process.
+#[derive(Debug, Clone)]
+pub struct Fd {
+    pub fd: u32,
+    pub kind: FdKind,
+    /// Target of the `/proc/<pid>/fd/<fd>` link, e.g. `/var/log/syslog` or `socket:[1234]`.
+    pub target: String,
+    /// File position from `fdinfo`.
+    pub pos: Option<u64>,
+    /// `open(2)` flags from `fdinfo`.
+    pub flags: Option<u32>,
+}
+
+impl Fd {
+    /// Inode of a socket, which links it to the entries of `/proc/net/*`.
+    pub fn socket_inode(&self) -> Option<u64> {
+        socket_inode(&self.target)
+    }
+}
+
+fn socket_inode(target: &str) -> Option<u64> {
+    target
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+/// Inodes of the sockets `pid` has open. Cheaper than `fds` because `fdinfo` is not read.
+pub fn socket_inodes(pid: Pid) -> Vec<u64> {
+    let Ok(entries) = fs::read_dir(format!("/proc/{}/fd", pid)) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|entry| {
+            let target = fs::read_link(entry.ok()?.path()).ok()?;
+            socket_inode(target.to_str()?)
+        })
+        .collect()
+}
+
+/// Open file descriptors of `pid` from `/proc/<pid>/fd` and `/proc/<pid>/fdinfo`,
+/// sorted by number. Descriptors closed while listing are skipped.
+pub fn fds(pid: Pid) -> std::io::Result<Vec<Fd>> {
+    let mut fds: Vec<_> = fs::read_dir(format!("/proc/{}/fd", pid))?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let fd = entry.file_name().to_str()?.parse().ok()?;
+            let target = fs::read_link(entry.path()).ok()?;
+            let target = target.to_string_lossy().to_string();
+            let fdinfo = fs::read_to_string(format!("/proc/{}/fdinfo/{}", pid, fd)).ok();
+            let field = |name: &str| {
+                fdinfo
+                    .as_deref()?
+                    .lines()
+                    .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+                    .map(str::trim)
+            };
+            Some(Fd {
+                fd,
+                kind: FdKind::from_target(&target),
+                pos: field("pos").and_then(|pos| pos.parse().ok()),
+                // Printed in octal
+                flags: field("flags").and_then(|flags| u32::from_str_radix(flags, 8).ok()),
+                target,
+            })
+        })
+        .collect();
+    fds.sort_by_key(|fd| fd.fd);
+    Ok(fds)
+}
+
+/// Access mode and the most useful status flags, like `rw,append,nonblock,cloexec`.
+pub fn format_open_flags(flags: u32) -> String {
+    let flags = flags as i32;
+    let mut names = vec![match flags & libc::O_ACCMODE {
+        libc::O_RDONLY => "r",
+        libc::O_WRONLY => "w",
+        _ => "rw",
+    }];
+    for (flag, name) in [
+        (libc::O_APPEND, "append"),
+        (libc::O_NONBLOCK, "nonblock"),
+        (libc::O_CLOEXEC, "cloexec"),
+        (libc::O_SYNC, "sync"),
+    ] {
+        if flags & flag == flag {
+            names.push(name);
+        }
+    }
+    names.join(",")
+}
+
+/// Memory totals in kB, of a whole process from `smaps_rollup` or of mappings from `smaps`.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryUsage {
+    pub rss: u64,
+    pub pss: u64,
+    pub pss_anon: u64,
+    pub pss_file: u64,
+    pub pss_shmem: u64,
+    pub shared_clean: u64,
+    pub shared_dirty: u64,
+    pub private_clean: u64,
+    pub private_dirty: u64,
+    pub swap: u64,
+    pub swap_pss: u64,
+}
+
+impl MemoryUsage {
+    /// Unique set size: memory only this process uses, freed if it exits.
+    pub fn uss(&self) -> u64 {
+        self.private_clean + self.private_dirty
+    }
+
+    pub fn add(&mut self, other: &MemoryUsage) {
+        self.rss += other.rss;
+        self.pss += other.pss;
+        self.pss_anon += other.pss_anon;
+        self.pss_file += other.pss_file;
+        self.pss_shmem += other.pss_shmem;
+        self.shared_clean += other.shared_clean;
+        self.shared_dirty += other.shared_dirty;
+        self.private_clean += other.private_clean;
+        self.private_dirty += other.private_dirty;
+        self.swap += other.swap;
+        self.swap_pss += other.swap_pss;
+    }
+
+    /// Takes a `Pss:    123 kB` line into account, other lines are ignored.
+    fn parse_line(&mut self, line: &str) {
+        let mut words = line.split_whitespace();
+        let (Some(key), Some(Ok(kb))) = (words.next(), words.next().map(str::parse::<u64>)) else {
+            return;
+        };
+        let field = match key {
+            "Rss:" => &mut self.rss,
+            "Pss:" => &mut self.pss,
+            "Pss_Anon:" => &mut self.pss_anon,
+            "Pss_File:" => &mut self.pss_file,
+            "Pss_Shmem:" => &mut self.pss_shmem,
+            "Shared_Clean:" => &mut self.shared_clean,
+            "Shared_Dirty:" => &mut self.shared_dirty,
+            "Private_Clean:" => &mut self.private_clean,
+            "Private_Dirty:" => &mut self.private_dirty,
+            "Swap:" => &mut self.swap,
+            "SwapPss:" => &mut self.swap_pss,
+            _ => return,
+        };
+        *field += kb;
+    }
+}
+
+/// Totals of `/proc/<pid>/smaps_rollup`. Needs the same access as ptrace, so other
+/// users' processes can usually only be read by root.
+pub fn smaps_rollup(pid: Pid) -> std::io::Result<MemoryUsage> {
+    let mut usage = MemoryUsage::default();
+    for line in fs::read_to_string(format!("/proc/{}/smaps_rollup", pid))?.lines() {
+        usage.parse_line(line);
+    }
+    Ok(usage)
+}
+
+/// Mappings of `/proc/<pid>/smaps` with their file (or `[heap]`, `[stack]`, `[anon]`).
+pub fn smaps(pid: Pid) -> std::io::Result<Vec<(String, MemoryUsage)>> {
+    let smaps = fs::read_to_string(format!("/proc/{}/smaps", pid))?;
+    Ok(parse_smaps(&smaps))
+}
+
+fn parse_smaps(smaps: &str) -> Vec<(String, MemoryUsage)> {
+    let mut mappings: Vec<(String, MemoryUsage)> = vec![];
+    for line in smaps.lines() {
+        let first = line.split_whitespace().next().unwrap_or_default();
+        if first.ends_with(':') {
+            if let Some((_, usage)) = mappings.last_mut() {
+                usage.parse_line(line);
+            }
+        } else {
+            // `start-end perms offset dev inode path`, the path may contain spaces
+            let path = line
+                .split_whitespace()
+                .skip(5)
+                .collect::<Vec<_>>()
+                .join(" ");
+            let path = if path.is_empty() {
+                "[anon]".to_string()
+            } else {
+                path
+            };
+            mappings.push((path, MemoryUsage::default()));
+        }
+    }
+    mappings
+}
+
+/// Variables of `/proc/<pid>/environ` as `(name, value)`, in the order the process has them.
+pub fn environ(pid: Pid) -> std::io::Result<Vec<(String, String)>> {
+    let environ = fs::read(format!("/proc/{}/environ", pid))?;
+    Ok(environ
+        .split(|byte| *byte == 0)
+        .filter(|var| !var.is_empty())
+        .map(|var| {
+            let var = String::from_utf8_lossy(var);
+            match var.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None => (var.to_string(), String::new()),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_memory_lines() {
+        let rollup = "\
+55d4c5a0e000-7ffc8b9fe000 ---p 00000000 00:00 0                          [rollup]
+Rss:               12344 kB
+Pss:                4120 kB
+Pss_Anon:           3000 kB
+Pss_File:           1100 kB
+Pss_Shmem:            20 kB
+Shared_Clean:       8000 kB
+Shared_Dirty:        200 kB
+Private_Clean:       144 kB
+Private_Dirty:      4000 kB
+Referenced:        12000 kB
+Anonymous:          4000 kB
+Swap:                 64 kB
+SwapPss:              32 kB
+Locked:                0 kB";
+        let mut usage = MemoryUsage::default();
+        for line in rollup.lines() {
+            usage.parse_line(line);
+        }
+        assert_eq!(usage.rss, 12344);
+        assert_eq!(usage.pss, 4120);
+        assert_eq!(usage.pss_anon, 3000);
+        assert_eq!(usage.pss_file, 1100);
+        assert_eq!(usage.pss_shmem, 20);
+        assert_eq!(usage.shared_clean, 8000);
+        assert_eq!(usage.shared_dirty, 200);
+        assert_eq!(usage.swap, 64);
+        assert_eq!(usage.swap_pss, 32);
+        assert_eq!(usage.uss(), 4144);
+    }
+
+    #[test]
+    fn ignores_lines_without_a_size() {
+        let mut usage = MemoryUsage::default();
+        usage.parse_line("VmFlags: rd ex mr mw me dw sd");
+        usage.parse_line("THPeligible:    0");
+        usage.parse_line("Rss:");
+        assert_eq!(usage.rss, 0);
+        assert_eq!(usage.uss(), 0);
+    }
+
+    #[test]
+    fn parses_smaps_per_mapping() {
+        let smaps = "\
+55d4c5a0e000-55d4c5a10000 r-xp 00000000 08:01 1048602                    /usr/bin/my tool
+Rss:                   8 kB
+Private_Clean:         8 kB
+VmFlags: rd ex mr mw me dw sd
+55d4c6b2f000-55d4c6b50000 rw-p 00000000 00:00 0                          [heap]
+Rss:                 132 kB
+Private_Dirty:       132 kB
+7f1e2c000000-7f1e2c021000 rw-p 00000000 00:00 0 
+Rss:                   4 kB
+Private_Dirty:         4 kB
+Swap:                 12 kB";
+        let mappings = parse_smaps(smaps);
+        let paths: Vec<_> = mappings.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, ["/usr/bin/my tool", "[heap]", "[anon]"]);
+        assert_eq!(mappings[0].1.private_clean, 8);
+        assert_eq!(mappings[1].1.uss(), 132);
+        assert_eq!(mappings[2].1.rss, 4);
+        assert_eq!(mappings[2].1.swap, 12);
+    }
+}
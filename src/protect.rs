@@ -0,0 +1,72 @@
+//! Processes that must not be signalled by accident.
+
+use color_eyre::{eyre::WrapErr, Result};
+use serde::{Deserialize, Serialize};
+use sysinfo::Process;
+
+use crate::columns::{process_cmd, process_user};
+
+/// One entry of the `protected` list in the config file, e.g. `{ name = "sshd" }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rule {
+    Pid(u32),
+    /// Exact process name.
+    Name(String),
+    User(String),
+    /// Regular expression over the command line (the name for processes without one).
+    Regex(String),
+}
+
+/// The compiled `protected` list. PID 1 and procman itself are always protected.
+#[derive(Debug, Default)]
+pub struct Protection {
+    rules: Vec<Compiled>,
+}
+
+#[derive(Debug)]
+enum Compiled {
+    Pid(u32),
+    Name(String),
+    User(String),
+    Regex(regex::Regex),
+}
+
+impl Protection {
+    pub fn new(rules: &[Rule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                Ok(match rule {
+                    Rule::Pid(pid) => Compiled::Pid(*pid),
+                    Rule::Name(name) => Compiled::Name(name.clone()),
+                    Rule::User(user) => Compiled::User(user.clone()),
+                    Rule::Regex(regex) => Compiled::Regex(
+                        regex::Regex::new(regex)
+                            .wrap_err_with(|| format!("invalid protected regex {:?}", regex))?,
+                    ),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn protects(&self, process: &Process) -> bool {
+        let pid = process.pid().as_u32();
+        pid == 1
+            || pid == std::process::id()
+            || self.rules.iter().any(|rule| match rule {
+                Compiled::Pid(protected) => pid == *protected,
+                Compiled::Name(name) => process.name().to_string_lossy() == name.as_str(),
+                Compiled::User(user) => process_user(process) == *user,
+                Compiled::Regex(regex) => {
+                    let cmd = process_cmd(process);
+                    if cmd.is_empty() {
+                        regex.is_match(&process.name().to_string_lossy())
+                    } else {
+                        regex.is_match(&cmd)
+                    }
+                }
+            })
+    }
+}
@@ -0,0 +1,187 @@
+use sysinfo::Process;
+
+use crate::columns::{process_cmd, process_user};
+use crate::procfs::Info;
+
+/// A parsed search or find query.
+///
+/// Words of the form `key:value` narrow the match to one field (`user:deploy`,
+/// `status:zombie`, `port:8080`, `env:RUST_LOG=debug`), everything else is matched as a case-insensitive substring
+/// against the table cells.
+#[derive(Debug, Default, Clone)]
+pub struct Query {
+    text: String,
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    User(String),
+    Status(String),
+    /// A TCP or UDP socket bound to this local port.
+    Port(u16),
+    /// An environment variable, with this value unless `None`.
+    Env(String, Option<String>),
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Self {
+        let mut text = vec![];
+        let mut terms = vec![];
+        for word in input.split_whitespace() {
+            let word = word.to_lowercase();
+            match word.split_once(':') {
+                // A term that is still being typed should not hide everything
+                Some(("user" | "status" | "port" | "env", "")) => {}
+                Some(("user", value)) => terms.push(Term::User(value.to_string())),
+                Some(("status", value)) => terms.push(Term::Status(value.to_string())),
+                Some(("env", value)) => terms.push(match value.split_once('=') {
+                    Some((name, "")) => Term::Env(name.to_string(), None),
+                    Some((name, value)) => Term::Env(name.to_string(), Some(value.to_string())),
+                    None => Term::Env(value.to_string(), None),
+                }),
+                Some(("port", value)) => match value.parse() {
+                    Ok(port) => terms.push(Term::Port(port)),
+                    Err(_) => text.push(word.clone()),
+                },
+                _ => text.push(word),
+            }
+        }
+        Self {
+            text: text.join(" "),
+            terms,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty() && self.terms.is_empty()
+    }
+
+    /// Whether matching needs the socket ports in `Info`, which are not always read.
+    pub fn uses_ports(&self) -> bool {
+        self.terms.iter().any(|term| matches!(term, Term::Port(_)))
+    }
+
+    /// In `full` mode the free text is also matched against the command line,
+    /// executable path and working directory.
+    pub fn matches(&self, process: &Process, info: Option<&Info>, full: bool) -> bool {
+        self.terms.iter().all(|term| term.matches(process, info))
+            && self.matches_text(process, full)
+    }
+
+    fn matches_text(&self, process: &Process, full: bool) -> bool {
+        if self.text.is_empty() {
+            return true;
+        }
+        let mut cells = vec![
+            process.pid().to_string(),
+            process.name().to_string_lossy().to_string(),
+            process_user(process),
+            format!("{:.1}%", process.cpu_usage()),
+            format!("{:.1}", process.memory() as f64 / 1024.0 / 1024.0),
+        ];
+        if full {
+            cells.push(process_cmd(process));
+            cells.extend(process.exe().map(|p| p.to_string_lossy().to_string()));
+            cells.extend(process.cwd().map(|p| p.to_string_lossy().to_string()));
+        }
+        cells
+            .iter()
+            .any(|cell| cell.to_lowercase().contains(&self.text))
+    }
+}
+
+impl Term {
+    fn matches(&self, process: &Process, info: Option<&Info>) -> bool {
+        match self {
+            Term::User(user) => process_user(process).to_lowercase() == *user,
+            Term::Status(status) => process
+                .status()
+                .to_string()
+                .to_lowercase()
+                .starts_with(status.as_str()),
+            Term::Port(port) => info.is_some_and(|info| info.local_ports.contains(port)),
+            // The query is lowercased, so names and values are compared ignoring case
+            Term::Env(name, value) => process.environ().iter().any(|var| {
+                let var = var.to_string_lossy().to_lowercase();
+                match (var.split_once('='), value) {
+                    (Some((var_name, var_value)), Some(value)) => {
+                        var_name == name && var_value == value
+                    }
+                    (Some((var_name, _)), None) => var_name == name,
+                    (None, _) => false,
+                }
+            }),
+        }
+    }
+}
+
+/// How the kill-by-pattern dialog interprets its input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PatternMode {
+    /// Exact process name, like `pkill -x`.
+    #[default]
+    Name,
+    /// Regular expression over the full command line, like `pkill -f`.
+    Regex,
+    /// The same syntax as the search bar.
+    Query,
+}
+
+impl PatternMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            PatternMode::Name => "exact name",
+            PatternMode::Regex => "regex on command line",
+            PatternMode::Query => "search query",
+        }
+    }
+
+    pub fn next(self) -> PatternMode {
+        match self {
+            PatternMode::Name => PatternMode::Regex,
+            PatternMode::Regex => PatternMode::Query,
+            PatternMode::Query => PatternMode::Name,
+        }
+    }
+}
+
+/// A compiled kill-by-pattern input.
+#[derive(Debug)]
+pub enum Pattern {
+    Name(String),
+    Regex(regex::Regex),
+    Query(Query),
+}
+
+impl Pattern {
+    /// `None` for an empty input, which matches nothing.
+    pub fn new(mode: PatternMode, input: &str) -> Result<Option<Self>, String> {
+        if input.trim().is_empty() {
+            return Ok(None);
+        }
+        let pattern = match mode {
+            PatternMode::Name => Pattern::Name(input.trim().to_string()),
+            PatternMode::Regex => {
+                Pattern::Regex(regex::Regex::new(input).map_err(|err| err.to_string())?)
+            }
+            PatternMode::Query => Pattern::Query(Query::parse(input)),
+        };
+        Ok(Some(pattern))
+    }
+
+    pub fn matches(&self, process: &Process, info: Option<&Info>) -> bool {
+        match self {
+            Pattern::Name(name) => process.name().to_string_lossy() == name.as_str(),
+            Pattern::Regex(regex) => {
+                let cmd = process_cmd(process);
+                if cmd.is_empty() {
+                    regex.is_match(&process.name().to_string_lossy())
+                } else {
+                    regex.is_match(&cmd)
+                }
+            }
+            Pattern::Query(query) => query.matches(process, info, true),
+        }
+    }
+}
@@ -0,0 +1,374 @@
+//! Changing the scheduling of processes.
+
+use sysinfo::Pid;
+
+use crate::{procfs, signals};
+
+/// Sets the nice value of every thread of `pid`, after checking that the PID still
+/// belongs to the process that started at `start_time`. Linux keeps the nice value per
+/// thread, so `setpriority` on the PID alone would only renice the main thread.
+pub fn set_nice(pid: Pid, start_time: Option<u64>, nice: i32) -> std::io::Result<()> {
+    signals::verify_identity(pid, start_time)?;
+    each_thread(pid, true, |tid| {
+        // SAFETY: setpriority(2) has no memory safety requirements
+        check(unsafe { libc::setpriority(libc::PRIO_PROCESS, tid.as_u32(), nice) } as i64)
+    })
+}
+
+/// Number of CPUs configured in the system, the size of the affinity grid.
+pub fn cpu_count() -> usize {
+    // SAFETY: sysconf has no memory safety requirements
+    let count = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_CONF) };
+    usize::try_from(count).unwrap_or(1).max(1)
+}
+
+/// CPUs the thread `pid` may run on, from `sched_getaffinity`.
+pub fn affinity(pid: Pid) -> std::io::Result<Vec<usize>> {
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: cpu_set_t is plain data, all zeroes is the empty set
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        // SAFETY: the size passed is the size of `set`
+        check(unsafe {
+            libc::sched_getaffinity(
+                pid.as_u32() as libc::pid_t,
+                std::mem::size_of::<libc::cpu_set_t>(),
+                &mut set,
+            )
+        } as i64)?;
+        // SAFETY: CPU_ISSET only reads the set and checks the index against its size
+        Ok((0..libc::CPU_SETSIZE as usize)
+            .filter(|cpu| unsafe { libc::CPU_ISSET(*cpu, &set) })
+            .collect())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = pid;
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Restricts `pid` to `cpus` with `sched_setaffinity`, after checking that the PID still
+/// belongs to the process that started at `start_time`. Affinity is per thread: without
+/// `all_threads` only the main thread (and threads it creates later) is changed.
+pub fn set_affinity(
+    pid: Pid,
+    start_time: Option<u64>,
+    cpus: &[usize],
+    all_threads: bool,
+) -> std::io::Result<()> {
+    signals::verify_identity(pid, start_time)?;
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: cpu_set_t is plain data, all zeroes is the empty set
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        for cpu in cpus {
+            // SAFETY: CPU_SET checks the index against the size of the set
+            unsafe { libc::CPU_SET(*cpu, &mut set) };
+        }
+        each_thread(pid, all_threads, |tid| {
+            // SAFETY: the size passed is the size of `set`
+            check(unsafe {
+                libc::sched_setaffinity(
+                    tid.as_u32() as libc::pid_t,
+                    std::mem::size_of::<libc::cpu_set_t>(),
+                    &set,
+                )
+            } as i64)
+        })
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (cpus, all_threads);
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// I/O scheduling class, see ioprio_set(2).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IoClass {
+    /// No class set, the level follows the nice value within best-effort.
+    #[default]
+    None,
+    Realtime,
+    BestEffort,
+    Idle,
+}
+
+impl IoClass {
+    pub fn next(self) -> IoClass {
+        match self {
+            IoClass::None => IoClass::Realtime,
+            IoClass::Realtime => IoClass::BestEffort,
+            IoClass::BestEffort => IoClass::Idle,
+            IoClass::Idle => IoClass::None,
+        }
+    }
+
+    /// Whether the class uses the level (0 highest … 7 lowest).
+    pub fn has_level(self) -> bool {
+        matches!(self, IoClass::Realtime | IoClass::BestEffort)
+    }
+}
+
+/// Class and level as passed to `ioprio_set`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IoPriority {
+    pub class: IoClass,
+    pub level: u8,
+}
+
+impl IoPriority {
+    const CLASS_SHIFT: u32 = 13;
+
+    fn from_raw(raw: i64) -> Self {
+        let class = match raw >> Self::CLASS_SHIFT {
+            1 => IoClass::Realtime,
+            2 => IoClass::BestEffort,
+            3 => IoClass::Idle,
+            _ => IoClass::None,
+        };
+        Self {
+            class,
+            level: (raw & 0x7) as u8,
+        }
+    }
+
+    fn raw(self) -> i64 {
+        let class = match self.class {
+            IoClass::None => 0,
+            IoClass::Realtime => 1,
+            IoClass::BestEffort => 2,
+            IoClass::Idle => 3,
+        };
+        let level = if self.class.has_level() {
+            self.level
+        } else {
+            0
+        };
+        (class << Self::CLASS_SHIFT) | i64::from(level.min(7))
+    }
+}
+
+/// `be/4`, `rt/0`, `idle` or `none` like ionice prints them.
+impl std::fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.class {
+            IoClass::None => write!(f, "none"),
+            IoClass::Realtime => write!(f, "rt/{}", self.level),
+            IoClass::BestEffort => write!(f, "be/{}", self.level),
+            IoClass::Idle => write!(f, "idle"),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+const IOPRIO_WHO_PROCESS: i64 = 1;
+
+/// I/O priority of the thread `pid`, from `ioprio_get`.
+pub fn io_priority(pid: Pid) -> std::io::Result<IoPriority> {
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: ioprio_get takes no pointers
+        let raw = unsafe {
+            libc::syscall(
+                libc::SYS_ioprio_get,
+                IOPRIO_WHO_PROCESS,
+                pid.as_u32() as i64,
+            )
+        };
+        check(raw)?;
+        Ok(IoPriority::from_raw(raw))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = pid;
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Sets the I/O priority of every thread of `pid` with `ioprio_set`, after checking
+/// that the PID still belongs to the process that started at `start_time`.
+pub fn set_io_priority(
+    pid: Pid,
+    start_time: Option<u64>,
+    priority: IoPriority,
+) -> std::io::Result<()> {
+    signals::verify_identity(pid, start_time)?;
+    #[cfg(target_os = "linux")]
+    {
+        each_thread(pid, true, |tid| {
+            // SAFETY: ioprio_set takes no pointers
+            check(unsafe {
+                libc::syscall(
+                    libc::SYS_ioprio_set,
+                    IOPRIO_WHO_PROCESS,
+                    tid.as_u32() as i64,
+                    priority.raw(),
+                )
+            })
+        })
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = priority;
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Scheduling policy, see sched(7).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Policy {
+    #[default]
+    Other,
+    Batch,
+    Idle,
+    Fifo,
+    RoundRobin,
+    Deadline,
+}
+
+impl Policy {
+    pub fn from_raw(raw: i64) -> Option<Self> {
+        match raw {
+            0 => Some(Policy::Other),
+            1 => Some(Policy::Fifo),
+            2 => Some(Policy::RoundRobin),
+            3 => Some(Policy::Batch),
+            5 => Some(Policy::Idle),
+            6 => Some(Policy::Deadline),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Policy::Other => "OTHER",
+            Policy::Batch => "BATCH",
+            Policy::Idle => "IDLE",
+            Policy::Fifo => "FIFO",
+            Policy::RoundRobin => "RR",
+            Policy::Deadline => "DEADLINE",
+        }
+    }
+
+    /// Cycles through the policies that can be set with `sched_setscheduler`.
+    pub fn next(self) -> Policy {
+        match self {
+            Policy::Other => Policy::Batch,
+            Policy::Batch => Policy::Idle,
+            Policy::Idle => Policy::Fifo,
+            Policy::Fifo => Policy::RoundRobin,
+            Policy::RoundRobin | Policy::Deadline => Policy::Other,
+        }
+    }
+
+    /// Real-time policies take a priority from 1 to 99, the others only 0.
+    pub fn is_realtime(self) -> bool {
+        matches!(self, Policy::Fifo | Policy::RoundRobin)
+    }
+}
+
+/// Sets the scheduling policy and real-time priority of every thread of `pid` with
+/// `sched_setscheduler`, after checking that the PID still belongs to the process that
+/// started at `start_time`.
+pub fn set_scheduler(
+    pid: Pid,
+    start_time: Option<u64>,
+    policy: Policy,
+    priority: i32,
+) -> std::io::Result<()> {
+    signals::verify_identity(pid, start_time)?;
+    #[cfg(target_os = "linux")]
+    {
+        let raw = match policy {
+            Policy::Other => libc::SCHED_OTHER,
+            Policy::Batch => libc::SCHED_BATCH,
+            Policy::Idle => libc::SCHED_IDLE,
+            Policy::Fifo => libc::SCHED_FIFO,
+            Policy::RoundRobin => libc::SCHED_RR,
+            // Needs sched_setattr with runtime, deadline and period
+            Policy::Deadline => return Err(std::io::ErrorKind::Unsupported.into()),
+        };
+        let param = libc::sched_param {
+            sched_priority: priority,
+        };
+        each_thread(pid, true, |tid| {
+            // SAFETY: `param` outlives the call
+            check(
+                unsafe { libc::sched_setscheduler(tid.as_u32() as libc::pid_t, raw, &param) }
+                    as i64,
+            )
+        })
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (policy, priority);
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Runs `apply` for `pid` and, with `all_threads`, for its other threads too.
+/// Threads that exit in the meantime are not an error.
+fn each_thread(
+    pid: Pid,
+    all_threads: bool,
+    mut apply: impl FnMut(Pid) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let tids = if all_threads {
+        procfs::threads(pid)
+    } else {
+        vec![pid]
+    };
+    for tid in tids {
+        if let Err(err) = apply(tid) {
+            if tid == pid || err.raw_os_error() != Some(libc::ESRCH) {
+                return Err(err);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Turns the -1 of a failed call into the error in errno.
+fn check(ret: i64) -> std::io::Result<()> {
+    if ret < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// CPU list in the `taskset -c` format, e.g. `0-3,6`.
+pub fn format_cpus(cpus: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    for cpu in cpus {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == *cpu => *end = *cpu,
+            _ => ranges.push((*cpu, *cpu)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Short description of a failed scheduling change, led by the errno name.
+pub fn describe_error(err: &std::io::Error) -> String {
+    match err.raw_os_error() {
+        Some(libc::EACCES) => "EACCES: raising priority needs root or CAP_SYS_NICE".to_string(),
+        Some(libc::EPERM) => {
+            "EPERM: not permitted (another user's process, or needs root)".to_string()
+        }
+        Some(libc::EINVAL) => "EINVAL: none of the chosen CPUs is usable".to_string(),
+        _ => signals::describe_error(err),
+    }
+}
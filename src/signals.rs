@@ -0,0 +1,323 @@
+use sysinfo::{Pid, Signal, SUPPORTED_SIGNALS};
+
+/// A signal that can be picked from the kill modal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignalInfo {
+    pub number: i32,
+    /// Name without the `SIG` prefix.
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// Names that share their number with another signal, listed after the canonical one.
+const ALIASES: [&str; 2] = ["IOT", "POLL"];
+
+/// Every signal sysinfo supports on this platform, in signal number order.
+/// Aliases such as IOT (same as ABRT) are left out, so each number appears once.
+pub fn all() -> Vec<SignalInfo> {
+    let mut signals = with_aliases();
+    signals.dedup_by_key(|s| s.number);
+    signals
+}
+
+/// Like `all`, but aliases are kept, each right after its canonical name.
+fn with_aliases() -> Vec<SignalInfo> {
+    let mut signals: Vec<_> = SUPPORTED_SIGNALS.iter().filter_map(|s| info(*s)).collect();
+    signals.sort_by_key(|s| (s.number, ALIASES.contains(&s.name)));
+    signals
+}
+
+/// Looks up a signal by number (`9`) or name (`KILL`, `sigkill`).
+/// Numbers without a name are accepted as long as they are valid signal numbers.
+pub fn parse(input: &str) -> Option<i32> {
+    let input = input.trim();
+    if let Ok(number) = input.parse::<i32>() {
+        return (1..=max_signal()).contains(&number).then_some(number);
+    }
+    let upper = input.to_uppercase();
+    let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+    with_aliases()
+        .into_iter()
+        .find(|s| s.name == name)
+        .map(|s| s.number)
+}
+
+/// `SIGKILL` for known signals, `signal 42` for everything else.
+pub fn name(number: i32) -> String {
+    all()
+        .into_iter()
+        .find(|s| s.number == number)
+        .map(|s| format!("SIG{}", s.name))
+        .unwrap_or_else(|| format!("signal {}", number))
+}
+
+/// A process to signal, pinned to the exact process the user picked so that a
+/// recycled PID is never signalled by mistake.
+///
+/// On Linux a pidfd is opened when the target is pinned and the signal goes through
+/// `pidfd_send_signal`, which cannot reach another process even if the PID is reused.
+/// Elsewhere (or on kernels without pidfds) the start time is checked right before `kill`.
+#[derive(Debug)]
+pub struct Target {
+    pub pid: Pid,
+    /// Start time (seconds since the epoch) the process had when it was picked,
+    /// `None` when any process with this PID is fine (e.g. a PID typed by hand).
+    pub start_time: Option<u64>,
+    /// Process group read right after the target was pinned, for `killpg`.
+    /// `None` for kernel threads and targets pinned outside a group kill.
+    pub pgid: Option<Pid>,
+    #[cfg(target_os = "linux")]
+    pidfd: Option<std::os::fd::OwnedFd>,
+}
+
+impl Target {
+    pub fn pin(pid: Pid, start_time: Option<u64>) -> std::io::Result<Self> {
+        // Open first and verify afterwards: if the process still has the expected start
+        // time once the descriptor exists, the descriptor refers to the picked process.
+        #[cfg(target_os = "linux")]
+        let pidfd = pidfd_open(pid).map_err(|err| {
+            // Keep describe_error from reading the errno as a signal failure
+            std::io::Error::new(err.kind(), format!("pidfd_open: {}", err))
+        })?;
+        verify_identity(pid, start_time)?;
+        Ok(Self {
+            pid,
+            start_time,
+            pgid: None,
+            #[cfg(target_os = "linux")]
+            pidfd,
+        })
+    }
+
+    pub fn signal(&self, number: i32) -> std::io::Result<()> {
+        #[cfg(target_os = "linux")]
+        if let Some(pidfd) = &self.pidfd {
+            return pidfd_send_signal(pidfd, number);
+        }
+        verify_identity(self.pid, self.start_time)?;
+        send(self.pid, number)
+    }
+}
+
+/// Fails if `pid` no longer has the expected `start_time`, i.e. the PID was reused.
+pub fn verify_identity(pid: Pid, start_time: Option<u64>) -> std::io::Result<()> {
+    let (Some(expected), Some(current)) = (start_time, crate::procfs::start_time(pid)) else {
+        return Ok(());
+    };
+    // Both sides are rounded to whole seconds from slightly different boot time readings
+    if current.abs_diff(expected) > 1 {
+        return Err(std::io::Error::other(
+            "PID now belongs to a different process, refusing",
+        ));
+    }
+    Ok(())
+}
+
+/// `Ok(None)` when the kernel has no pidfd support.
+#[cfg(target_os = "linux")]
+fn pidfd_open(pid: Pid) -> std::io::Result<Option<std::os::fd::OwnedFd>> {
+    use std::os::fd::FromRawFd;
+
+    // SAFETY: pidfd_open takes no pointers
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid.as_u32() as libc::pid_t, 0) };
+    if fd < 0 {
+        let err = std::io::Error::last_os_error();
+        return match err.raw_os_error() {
+            Some(libc::ENOSYS) => Ok(None),
+            _ => Err(err),
+        };
+    }
+    // SAFETY: the syscall returned a new descriptor that nothing else owns
+    Ok(Some(unsafe {
+        std::os::fd::OwnedFd::from_raw_fd(fd as i32)
+    }))
+}
+
+#[cfg(target_os = "linux")]
+fn pidfd_send_signal(pidfd: &std::os::fd::OwnedFd, number: i32) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    // SAFETY: a null siginfo is allowed and makes the call behave like kill(2)
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_pidfd_send_signal,
+            pidfd.as_raw_fd(),
+            number,
+            std::ptr::null::<libc::siginfo_t>(),
+            0,
+        )
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// Sends signal `number` to `pid`. Unlike `Process::kill_with` this accepts
+/// arbitrary (e.g. real-time) signal numbers.
+pub fn send(pid: Pid, number: i32) -> std::io::Result<()> {
+    // SAFETY: kill(2) has no memory safety requirements
+    let ret = unsafe { libc::kill(pid.as_u32() as libc::pid_t, number) };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// Sends signal `number` to every member of process group `pgid` with `killpg(2)`.
+/// Group 0 is refused, because `killpg(0, ..)` means the caller's own group.
+pub fn send_group(pgid: Pid, number: i32) -> std::io::Result<()> {
+    if pgid.as_u32() as libc::pid_t <= 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "no process group to signal",
+        ));
+    }
+    // SAFETY: killpg(2) has no memory safety requirements
+    let ret = unsafe { libc::killpg(pgid.as_u32() as libc::pid_t, number) };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// Which processes the kill modal signals besides the picked ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Scope {
+    /// Only the picked processes.
+    #[default]
+    Picked,
+    /// The picked processes and all their descendants, deepest first.
+    TreeLeavesFirst,
+    /// The picked processes and all their descendants, parents first.
+    TreeParentsFirst,
+    /// Every process in the process groups of the picked processes, via `killpg`.
+    Group,
+    /// Every process in the sessions of the picked processes.
+    Session,
+}
+
+impl Scope {
+    pub fn label(self) -> &'static str {
+        match self {
+            Scope::Picked => "selected only",
+            Scope::TreeLeavesFirst => "process tree, leaves first",
+            Scope::TreeParentsFirst => "process tree, parents first",
+            Scope::Group => "process group (killpg)",
+            Scope::Session => "session",
+        }
+    }
+
+    pub fn next(self) -> Scope {
+        match self {
+            Scope::Picked => Scope::TreeLeavesFirst,
+            Scope::TreeLeavesFirst => Scope::TreeParentsFirst,
+            Scope::TreeParentsFirst => Scope::Group,
+            Scope::Group => Scope::Session,
+            Scope::Session => Scope::Picked,
+        }
+    }
+}
+
+/// Short description of a failed `send`, led by the errno name (`EPERM: operation not permitted`).
+pub fn describe_error(err: &std::io::Error) -> String {
+    match err.raw_os_error() {
+        Some(libc::EPERM) => "EPERM: operation not permitted".to_string(),
+        Some(libc::ESRCH) => "ESRCH: no such process".to_string(),
+        Some(libc::EINVAL) => "EINVAL: invalid signal".to_string(),
+        _ => err.to_string(),
+    }
+}
+
+/// Whether the default action of the signal ends the process, so it makes sense
+/// to check afterwards that the target is gone.
+pub fn terminates(number: i32) -> bool {
+    ![
+        libc::SIGCHLD,
+        libc::SIGCONT,
+        libc::SIGSTOP,
+        libc::SIGTSTP,
+        libc::SIGTTIN,
+        libc::SIGTTOU,
+        libc::SIGURG,
+        libc::SIGWINCH,
+    ]
+    .contains(&number)
+}
+
+fn max_signal() -> i32 {
+    #[cfg(target_os = "linux")]
+    {
+        libc::SIGRTMAX()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        31
+    }
+}
+
+fn info(signal: Signal) -> Option<SignalInfo> {
+    let (number, name, description) = match signal {
+        Signal::Hangup => (
+            libc::SIGHUP,
+            "HUP",
+            "Hangup, many daemons reload their config",
+        ),
+        Signal::Interrupt => (libc::SIGINT, "INT", "Interrupt, like Ctrl+C"),
+        Signal::Quit => (
+            libc::SIGQUIT,
+            "QUIT",
+            "Quit with core dump, JVM thread dump",
+        ),
+        Signal::Illegal => (libc::SIGILL, "ILL", "Illegal instruction"),
+        Signal::Trap => (libc::SIGTRAP, "TRAP", "Trace/breakpoint trap"),
+        Signal::Abort => (libc::SIGABRT, "ABRT", "Abort with core dump"),
+        Signal::IOT => (libc::SIGIOT, "IOT", "Same as ABRT"),
+        Signal::Bus => (libc::SIGBUS, "BUS", "Bus error"),
+        Signal::FloatingPointException => (libc::SIGFPE, "FPE", "Floating point exception"),
+        Signal::Kill => (libc::SIGKILL, "KILL", "Kill immediately, cannot be caught"),
+        Signal::User1 => (libc::SIGUSR1, "USR1", "User defined, often log rotation"),
+        Signal::Segv => (libc::SIGSEGV, "SEGV", "Invalid memory reference"),
+        Signal::User2 => (libc::SIGUSR2, "USR2", "User defined, often log rotation"),
+        Signal::Pipe => (libc::SIGPIPE, "PIPE", "Broken pipe"),
+        Signal::Alarm => (libc::SIGALRM, "ALRM", "Timer expired"),
+        Signal::Term => (libc::SIGTERM, "TERM", "Terminate gracefully"),
+        #[cfg(target_os = "linux")]
+        Signal::Child => (libc::SIGCHLD, "CHLD", "Child stopped or terminated"),
+        Signal::Continue => (libc::SIGCONT, "CONT", "Continue if stopped"),
+        Signal::Stop => (libc::SIGSTOP, "STOP", "Stop (pause), cannot be caught"),
+        Signal::TSTP => (libc::SIGTSTP, "TSTP", "Stop typed at terminal, like Ctrl+Z"),
+        Signal::TTIN => (
+            libc::SIGTTIN,
+            "TTIN",
+            "Terminal input for background process",
+        ),
+        Signal::TTOU => (
+            libc::SIGTTOU,
+            "TTOU",
+            "Terminal output for background process",
+        ),
+        Signal::Urgent => (libc::SIGURG, "URG", "Urgent condition on socket"),
+        Signal::XCPU => (libc::SIGXCPU, "XCPU", "CPU time limit exceeded"),
+        Signal::XFSZ => (libc::SIGXFSZ, "XFSZ", "File size limit exceeded"),
+        Signal::VirtualAlarm => (libc::SIGVTALRM, "VTALRM", "Virtual alarm clock"),
+        Signal::Profiling => (libc::SIGPROF, "PROF", "Profiling timer expired"),
+        Signal::Winch => (libc::SIGWINCH, "WINCH", "Window resize"),
+        Signal::IO => (libc::SIGIO, "IO", "I/O now possible"),
+        #[cfg(target_os = "linux")]
+        Signal::Poll => (libc::SIGPOLL, "POLL", "Same as IO"),
+        #[cfg(target_os = "linux")]
+        Signal::Power => (libc::SIGPWR, "PWR", "Power failure"),
+        Signal::Sys => (libc::SIGSYS, "SYS", "Bad system call"),
+        #[allow(unreachable_patterns)]
+        _ => return None,
+    };
+    Some(SignalInfo {
+        number,
+        name,
+        description,
+    })
+}
@@ -0,0 +1,37 @@
+use std::collections::{HashMap, HashSet};
+
+use sysinfo::{Pid, Process};
+
+/// `root` followed by all of its descendants, parents before their children.
+pub fn subtree(processes: &HashMap<Pid, Process>, root: Pid) -> Vec<Pid> {
+    let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+    for (pid, process) in processes {
+        if let Some(parent) = process.parent() {
+            children.entry(parent).or_default().push(*pid);
+        }
+    }
+    for list in children.values_mut() {
+        list.sort();
+    }
+
+    let mut order = vec![root];
+    let mut next = 0;
+    while next < order.len() {
+        if let Some(list) = children.get(&order[next]) {
+            order.extend(list.iter().filter(|pid| **pid != root));
+        }
+        next += 1;
+    }
+    order
+}
+
+/// The subtrees of all `roots`, parents before their children. A process that is part of
+/// several subtrees (e.g. both a root and its parent were picked) is listed once.
+pub fn subtrees(processes: &HashMap<Pid, Process>, roots: &[Pid]) -> Vec<Pid> {
+    let mut seen = HashSet::new();
+    roots
+        .iter()
+        .flat_map(|root| subtree(processes, *root))
+        .filter(|pid| seen.insert(*pid))
+        .collect()
+}